@@ -6,7 +6,8 @@ extern crate sdl2_image;
 use std::path::Path;
 use std::rc::Rc;
 
-use glorious::{Behavior, BoxedInputMapper, Device, Game, Renderer, Sprite};
+use glorious::{Asset, Behavior, BoxedInputMapper, Device, Frame, Game, Queue, Renderer,
+               Sprite};
 use sdl2::keyboard::{Keycode, Scancode};
 use sdl2::rect::Rect;
 use sdl2_image::{INIT_PNG, INIT_JPG};
@@ -51,7 +52,7 @@ impl Player {
             right_down: false,
             up_down: false,
             down_down: false,
-            sprite: Sprite::new(Rc::new(texture), None),
+            sprite: Sprite::new(Rc::new(Asset::new(texture)), None),
         }
     }
 }
@@ -60,17 +61,21 @@ impl Behavior<GameState> for Player {
     type Message = Message;
 
     /// Updates the object each frame.
-    fn update(&mut self, _state: &mut GameState, _queue: &mut Vec<Self::Message>) {
+    fn update(&mut self,
+              _state: &mut GameState,
+              _frame: &Frame,
+              _queue: &mut Queue<Self::Message>) {
         self.rect.offset(self.vx, self.vy);
     }
 
     /// Handles new messages since the last frame.
-    fn handle(&mut self,
-              _state: &mut GameState,
-              message: Self::Message,
-              _queue: &mut Vec<Self::Message>) {
+    fn handle_ref(&mut self,
+                  _state: &mut GameState,
+                  _frame: &Frame,
+                  message: &Self::Message,
+                  _queue: &mut Queue<Self::Message>) {
         use self::Message::*;
-        match message {
+        match *message {
             StartMovingLeft => {
                 self.left_down = true;
                 if self.right_down {
@@ -140,7 +145,7 @@ impl Behavior<GameState> for Player {
     }
 
     /// Renders the object.
-    fn render(&mut self, _state: &GameState, renderer: &mut Renderer) {
+    fn render(&mut self, _state: &GameState, _frame: &Frame, renderer: &mut Renderer) {
         self.sprite.render(renderer, self.rect.x(), self.rect.y(), Some((128, 128)));
     }
 }
@@ -173,35 +178,39 @@ impl Behavior<GameState> for GameLogic {
     type Message = Message;
 
     /// Initializes the object when it is added to the game.
-    fn initialize(&mut self, state: &mut GameState, new_messages: &mut Vec<Self::Message>) {
+    fn initialize(&mut self,
+                  state: &mut GameState,
+                  frame: &Frame,
+                  new_messages: &mut Queue<Self::Message>) {
         println!("State example : {}", state.example);
 
         for object in self.objects.iter_mut() {
-            object.initialize(state, new_messages);
+            object.initialize(state, frame, new_messages);
         }
     }
 
     /// Updates the object each frame.
-    fn update(&mut self, state: &mut GameState, queue: &mut Vec<Self::Message>) {
+    fn update(&mut self, state: &mut GameState, frame: &Frame, queue: &mut Queue<Self::Message>) {
         for object in self.objects.iter_mut() {
-            object.update(state, queue);
+            object.update(state, frame, queue);
         }
     }
 
     /// Handles new messages since the last frame.
-    fn handle(&mut self,
-              state: &mut GameState,
-              message: Self::Message,
-              queue: &mut Vec<Self::Message>) {
+    fn handle_ref(&mut self,
+                  state: &mut GameState,
+                  frame: &Frame,
+                  message: &Self::Message,
+                  queue: &mut Queue<Self::Message>) {
         for object in self.objects.iter_mut() {
-            object.handle(state, message, queue);
+            object.handle_ref(state, frame, message, queue);
         }
     }
 
     /// Renders the object.
-    fn render(&mut self, state: &GameState, renderer: &mut Renderer) {
+    fn render(&mut self, state: &GameState, frame: &Frame, renderer: &mut Renderer) {
         for object in &mut self.objects {
-            object.render(state, renderer);
+            object.render(state, frame, renderer);
         }
     }
 }
@@ -260,5 +269,9 @@ fn main() {
     const MAX_FPS: u32 = 60;
     let mut game = Game::new(MAX_FPS, renderer, event_pump);
 
-    game.run(&mut state, &mapper, &mut logic, |m| *m == Message::Quit);
+    game.run(&mut state, &mapper, &mut logic, |m| if *m == Message::Quit {
+        Some(())
+    } else {
+        None
+    });
 }
@@ -0,0 +1,383 @@
+//! The crate `glorious_derive` provides `#[derive(Behavior)]` for
+//! composite behaviors of the `glorious` crate.
+//!
+//! For an enum, every method is forwarded to the child in the current
+//! variant. Each variant holds at most one child, and variants without
+//! one do nothing. For a struct, every method is forwarded to each
+//! field in order. Like in a tuple, messages are passed to each field
+//! by reference, unless the struct has a single field.
+//!
+//! The children all have to use the same message type, which becomes
+//! the message type of the composite. The following attributes are
+//! supported on fields and variants:
+//!
+//! - `#[behavior(skip)]` leaves a field or variant out, e.g. for plain
+//!   data.
+//! - `#[behavior(render_order = N)]` renders the fields of a struct by
+//!   ascending `N` instead of in declaration order. Fields without the
+//!   attribute have order `0`.
+//!
+//! As only the current variant of an enum is rendered, `render_order`
+//! is rejected on variants and their fields:
+//!
+//! ```compile_fail
+//! #[macro_use]
+//! extern crate glorious_derive;
+//! extern crate glorious;
+//!
+//! #[derive(Behavior)]
+//! enum Screen {
+//!     #[behavior(render_order = 1)]
+//!     Menu(Box<glorious::Behavior<(), Message = ()>>),
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! ```compile_fail
+//! #[macro_use]
+//! extern crate glorious_derive;
+//! extern crate glorious;
+//!
+//! #[derive(Behavior)]
+//! enum Screen {
+//!     Menu(#[behavior(render_order = 1)] Box<glorious::Behavior<(), Message = ()>>),
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate glorious_derive;
+//!
+//! #[derive(Behavior)]
+//! struct Level {
+//!     #[behavior(render_order = 1)]
+//!     hud: Hud,
+//!     player: Player,
+//!     enemies: Router<Enemy>,
+//!     #[behavior(skip)]
+//!     name: String,
+//! }
+//! ```
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as Tokens};
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Ident, Lit, Meta, NestedMeta, Type};
+
+/// Derives `glorious::Behavior` by forwarding to the children of an
+/// enum or struct.
+#[proc_macro_derive(Behavior, attributes(behavior))]
+pub fn derive_behavior(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// The options given to a field or variant with `#[behavior(..)]`.
+#[derive(Default)]
+struct Options {
+    skip: bool,
+    /// The order, along with the span of the option.
+    render_order: Option<(i64, Span)>,
+}
+
+impl Options {
+    fn parse(attrs: &[Attribute]) -> Result<Options, Error> {
+        let mut options = Options::default();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("behavior")) {
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(Error::new(meta.span(), "expected `behavior(..)`")),
+            };
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("skip") => {
+                        options.skip = true;
+                    }
+                    NestedMeta::Meta(Meta::NameValue(ref pair))
+                        if pair.path.is_ident("render_order") => {
+                        let order = match pair.lit {
+                            Lit::Int(ref int) => int.base10_parse()?,
+                            ref lit => return Err(Error::new(lit.span(), "expected an integer")),
+                        };
+                        options.render_order = Some((order, pair.span()));
+                    }
+                    nested => return Err(Error::new(nested.span(), "unknown behavior option")),
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    /// Fails if a render order was given, as only structs support it.
+    fn reject_render_order(&self) -> Result<(), Error> {
+        match self.render_order {
+            Some((_, span)) => {
+                Err(Error::new(span, "`render_order` is only supported on fields of structs"))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// A field that behavior methods are forwarded to.
+struct Child {
+    /// The expression or pattern binding of the field.
+    access: Tokens,
+    ty: Type,
+    render_order: i64,
+}
+
+fn expand(input: &DeriveInput) -> Result<Tokens, Error> {
+    let name = &input.ident;
+    let state = Ident::new("__S", Span::call_site());
+
+    let mut generics = input.generics.clone();
+    generics.params.push(syn::parse_quote!(#state));
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let (children, methods) = match input.data {
+        Data::Enum(ref data) => {
+            let mut children = Vec::new();
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let options = Options::parse(&variant.attrs)?;
+                options.reject_render_order()?;
+                if options.skip {
+                    arms.push((quote!(#name::#ident { .. }), false));
+                    continue;
+                }
+                let mut child = None;
+                let mut bindings = Vec::new();
+                for (index, field) in fields(&variant.fields)?.into_iter().enumerate() {
+                    let member = member(index, &field);
+                    field.options.reject_render_order()?;
+                    if field.options.skip {
+                        bindings.push(quote!(#member: _));
+                        continue;
+                    }
+                    if child.is_some() {
+                        return Err(Error::new(field.span,
+                                              "enum variants can only hold a single child"));
+                    }
+                    bindings.push(quote!(#member: ref mut child));
+                    child = Some(field.ty.clone());
+                }
+                let pattern = quote!(#name::#ident { #(#bindings,)* .. });
+                if let Some(ref ty) = child {
+                    children.push(Child {
+                        access: quote!(child),
+                        ty: ty.clone(),
+                        render_order: 0,
+                    });
+                }
+                arms.push((pattern, child.is_some()));
+            }
+            let methods = enum_methods(&arms, &state);
+            (children, methods)
+        }
+        Data::Struct(ref data) => {
+            let mut children = Vec::new();
+            for (index, field) in fields(&data.fields)?.into_iter().enumerate() {
+                if field.options.skip {
+                    continue;
+                }
+                let member = member(index, &field);
+                children.push(Child {
+                    access: quote!(self.#member),
+                    ty: field.ty.clone(),
+                    render_order: field.options.render_order.map_or(0, |(order, _)| order),
+                });
+            }
+            let methods = struct_methods(&children, &state);
+            (children, methods)
+        }
+        Data::Union(ref data) => {
+            return Err(Error::new(data.union_token.span, "unions cannot be behaviors"));
+        }
+    };
+
+    let first = match children.first() {
+        Some(child) => child.ty.clone(),
+        None => return Err(Error::new(name.span(), "a behavior needs at least one child")),
+    };
+    let message = quote!(<#first as ::glorious::Behavior<#state>>::Message);
+
+    // Bound each type only once, as the same type may appear repeatedly,
+    // and the first type in terms of its own message would not resolve
+    let mut bounds = vec![quote!(#first: ::glorious::Behavior<#state>)];
+    let mut seen = vec![quote!(#first).to_string()];
+    for child in &children {
+        let ty = &child.ty;
+        let key = quote!(#ty).to_string();
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+        bounds.push(quote!(#ty: ::glorious::Behavior<#state, Message = #message>));
+    }
+    let predicates = where_clause.map(|clause| {
+        let predicates = &clause.predicates;
+        quote!(#predicates,)
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::glorious::Behavior<#state> for #name #ty_generics
+            where #predicates #(#bounds,)*
+        {
+            type Message = #message;
+
+            #methods
+        }
+    })
+}
+
+/// A field of a struct or variant, along with its options.
+struct Field<'a> {
+    ident: Option<&'a Ident>,
+    ty: &'a Type,
+    span: Span,
+    options: Options,
+}
+
+fn fields<'a>(fields: &'a Fields) -> Result<Vec<Field<'a>>, Error> {
+    fields.iter()
+        .map(|field| {
+            Ok(Field {
+                ident: field.ident.as_ref(),
+                ty: &field.ty,
+                span: field.span(),
+                options: Options::parse(&field.attrs)?,
+            })
+        })
+        .collect()
+}
+
+/// Returns the name of a field, or its index for tuple fields.
+fn member(index: usize, field: &Field) -> Tokens {
+    match field.ident {
+        Some(ident) => quote!(#ident),
+        None => {
+            let index = syn::Index::from(index);
+            quote!(#index)
+        }
+    }
+}
+
+/// Forwards to the child bound in each arm, if the arm has one.
+fn enum_methods(arms: &[(Tokens, bool)], state: &Ident) -> Tokens {
+    let forward = |call: Tokens| {
+        let arms = arms.iter().map(|&(ref pattern, has_child)| {
+            if has_child {
+                quote!(#pattern => child.#call,)
+            } else {
+                quote!(#pattern => {})
+            }
+        });
+        quote! {
+            match *self {
+                #(#arms)*
+            }
+        }
+    };
+    let initialize = forward(quote!(initialize(state, frame, queue)));
+    let update = forward(quote!(update(state, frame, queue)));
+    let handle = forward(quote!(handle(state, frame, message, queue)));
+    let handle_ref = forward(quote!(handle_ref(state, frame, message, queue)));
+    let render = forward(quote!(render(state, frame, renderer)));
+    let render_window = forward(quote!(render_window(state, frame, window_id, renderer)));
+    methods(state, initialize, update, handle, handle_ref, render, render_window)
+}
+
+fn struct_methods(children: &[Child], state: &Ident) -> Tokens {
+    let forward = |children: &[&Child], call: &Tokens| {
+        let accesses = children.iter().map(|child| &child.access);
+        quote!(#(#accesses.#call;)*)
+    };
+    let all: Vec<&Child> = children.iter().collect();
+    let mut by_render_order = all.clone();
+    // Sorting is stable, so equal orders keep the declaration order
+    by_render_order.sort_by_key(|child| child.render_order);
+
+    let initialize = forward(&all, &quote!(initialize(state, frame, queue)));
+    let update = forward(&all, &quote!(update(state, frame, queue)));
+    let handle = if all.len() == 1 {
+        forward(&all, &quote!(handle(state, frame, message, queue)))
+    } else {
+        // Several children share the message, like in a tuple
+        let behavior = quote!(<Self as ::glorious::Behavior<#state>>);
+        quote!(#behavior::handle_ref(self, state, frame, &message, queue);)
+    };
+    let handle_ref = forward(&all, &quote!(handle_ref(state, frame, message, queue)));
+    let render = forward(&by_render_order, &quote!(render(state, frame, renderer)));
+    let render_window = forward(&by_render_order,
+                                &quote!(render_window(state, frame, window_id, renderer)));
+    methods(state, initialize, update, handle, handle_ref, render, render_window)
+}
+
+fn methods(state: &Ident,
+           initialize: Tokens,
+           update: Tokens,
+           handle: Tokens,
+           handle_ref: Tokens,
+           render: Tokens,
+           render_window: Tokens)
+           -> Tokens {
+    quote! {
+        fn initialize(&mut self,
+                      state: &mut #state,
+                      frame: &::glorious::Frame,
+                      queue: &mut ::glorious::Queue<Self::Message>) {
+            #initialize
+        }
+
+        fn update(&mut self,
+                  state: &mut #state,
+                  frame: &::glorious::Frame,
+                  queue: &mut ::glorious::Queue<Self::Message>) {
+            #update
+        }
+
+        fn handle(&mut self,
+                  state: &mut #state,
+                  frame: &::glorious::Frame,
+                  message: Self::Message,
+                  queue: &mut ::glorious::Queue<Self::Message>) {
+            #handle
+        }
+
+        fn handle_ref(&mut self,
+                      state: &mut #state,
+                      frame: &::glorious::Frame,
+                      message: &Self::Message,
+                      queue: &mut ::glorious::Queue<Self::Message>) {
+            #handle_ref
+        }
+
+        fn render(&mut self,
+                  state: &#state,
+                  frame: &::glorious::Frame,
+                  renderer: &mut ::glorious::Renderer) {
+            #render
+        }
+
+        fn render_window(&mut self,
+                         state: &#state,
+                         frame: &::glorious::Frame,
+                         window_id: u32,
+                         renderer: &mut ::glorious::Renderer) {
+            #render_window
+        }
+    }
+}
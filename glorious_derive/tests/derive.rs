@@ -0,0 +1,153 @@
+//! Tests of behaviors composed with `#[derive(Behavior)]`.
+
+extern crate glorious;
+#[macro_use]
+extern crate glorious_derive;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use glorious::{Behavior, Device, Frame, Queue, Renderer, Scheduler};
+
+/// Records the calls it gets in the state.
+struct Recorder(&'static str);
+
+impl Behavior<Vec<String>> for Recorder {
+    type Message = u32;
+
+    fn update(&mut self, state: &mut Vec<String>, _frame: &Frame, queue: &mut Queue<u32>) {
+        state.push(format!("{} update", self.0));
+        queue.push(1);
+    }
+
+    fn handle(&mut self,
+              state: &mut Vec<String>,
+              _frame: &Frame,
+              message: u32,
+              _queue: &mut Queue<u32>) {
+        state.push(format!("{} handle {}", self.0, message));
+    }
+
+    fn handle_ref(&mut self,
+                  state: &mut Vec<String>,
+                  _frame: &Frame,
+                  message: &u32,
+                  _queue: &mut Queue<u32>) {
+        state.push(format!("{} handle_ref {}", self.0, message));
+    }
+}
+
+#[derive(Behavior)]
+struct Pair {
+    first: Recorder,
+    #[behavior(skip)]
+    #[allow(dead_code)]
+    name: String,
+    #[behavior(render_order = -1)]
+    second: Recorder,
+}
+
+/// Records when it is rendered in a shared log.
+struct Layer(&'static str, Rc<RefCell<Vec<&'static str>>>);
+
+impl Behavior<()> for Layer {
+    type Message = ();
+
+    fn render(&mut self, _state: &(), _frame: &Frame, _renderer: &mut Renderer) {
+        self.1.borrow_mut().push(self.0);
+    }
+}
+
+#[derive(Behavior)]
+struct Layers {
+    #[behavior(render_order = 1)]
+    hud: Layer,
+    level: Layer,
+    #[behavior(render_order = -1)]
+    background: Layer,
+    #[behavior(render_order = 1)]
+    cursor: Layer,
+}
+
+#[derive(Behavior)]
+enum Screen {
+    Menu(Recorder),
+    Level {
+        #[behavior(skip)]
+        #[allow(dead_code)]
+        number: u32,
+        recorder: Recorder,
+    },
+    #[behavior(skip)]
+    #[allow(dead_code)]
+    Skipped(Recorder),
+    Empty,
+}
+
+fn run<B>(behavior: &mut B) -> (Vec<String>, Vec<u32>)
+    where B: Behavior<Vec<String>, Message = u32>
+{
+    let mut state = Vec::new();
+    let mut messages = Vec::new();
+    let mut scheduler = Scheduler::new();
+    let frame = Frame::new();
+    {
+        let mut queue = Queue::new(&mut messages, &mut scheduler);
+        behavior.update(&mut state, &frame, &mut queue);
+        behavior.handle(&mut state, &frame, 2, &mut queue);
+        behavior.handle_ref(&mut state, &frame, &3, &mut queue);
+    }
+    (state, messages)
+}
+
+#[test]
+fn struct_forwards_to_fields_in_order() {
+    let mut pair = Pair {
+        first: Recorder("first"),
+        name: "pair".to_owned(),
+        second: Recorder("second"),
+    };
+    let (calls, messages) = run(&mut pair);
+    assert_eq!(calls,
+               vec!["first update",
+                    "second update",
+                    "first handle 2",
+                    "second handle 2",
+                    "first handle_ref 3",
+                    "second handle_ref 3"]);
+    assert_eq!(messages, vec![1, 1]);
+}
+
+#[test]
+fn enum_forwards_to_current_variant() {
+    let (calls, _) = run(&mut Screen::Menu(Recorder("menu")));
+    assert_eq!(calls, vec!["menu update", "menu handle 2", "menu handle_ref 3"]);
+
+    let mut level = Screen::Level {
+        number: 1,
+        recorder: Recorder("level"),
+    };
+    let (calls, _) = run(&mut level);
+    assert_eq!(calls, vec!["level update", "level handle 2", "level handle_ref 3"]);
+}
+
+#[test]
+fn enum_ignores_skipped_and_empty_variants() {
+    assert_eq!(run(&mut Screen::Skipped(Recorder("skipped"))), (vec![], vec![]));
+    assert_eq!(run(&mut Screen::Empty), (vec![], vec![]));
+}
+
+#[test]
+fn struct_renders_fields_by_render_order() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut layers = Layers {
+        hud: Layer("hud", log.clone()),
+        level: Layer("level", log.clone()),
+        background: Layer("background", log.clone()),
+        cursor: Layer("cursor", log.clone()),
+    };
+    let device = Device::software(16, 16).unwrap();
+    let mut renderer = device.create_renderer();
+    layers.render(&(), &Frame::new(), &mut renderer);
+    assert_eq!(*log.borrow(), vec!["background", "level", "hud", "cursor"]);
+}
@@ -0,0 +1,397 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+use std::time::Duration;
+
+use sdl2_mixer::{self, Channel, Chunk, Music, DEFAULT_CHANNELS, DEFAULT_FORMAT,
+                 DEFAULT_FREQUENCY, MAX_VOLUME};
+
+use frame::Frame;
+use music::{Jukebox, MusicCommand, Track};
+use plugin::Plugin;
+use resources::ResourceManager;
+
+const CHUNK_SIZE: isize = 1024;
+
+/// A group of sounds sharing a volume.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum VolumeGroup {
+    /// Everything, scaling the volume of the other groups.
+    Master,
+    /// The music.
+    Music,
+    /// The sound effects.
+    Sfx,
+}
+
+/// A handle to the opened audio device, mixing sound effects on a fixed
+/// number of channels along with a single music track.
+///
+/// The state of `SDL2_mixer` is global, so only a single mixer should
+/// be opened at a time. Decoders for compressed formats have to be
+/// loaded with `sdl2_mixer::init` first, like for `sdl2_image`.
+///
+/// To run without a sound card, e.g. in tests, set the environment
+/// variable `SDL_AUDIODRIVER` to `dummy` before opening the mixer.
+#[derive(Debug)]
+pub struct Mixer {
+    channels: usize,
+    reserved: Cell<usize>,
+    master: Cell<f32>,
+    music: Cell<f32>,
+    sfx: Cell<f32>,
+    paused: Cell<bool>,
+}
+
+impl Mixer {
+    /// Opens the audio device with the default format, mixing
+    /// `channels` sound effects at once.
+    pub fn open(channels: usize) -> Result<Mixer, String> {
+        sdl2_mixer::open_audio(DEFAULT_FREQUENCY, DEFAULT_FORMAT, DEFAULT_CHANNELS, CHUNK_SIZE)?;
+        let channels = sdl2_mixer::allocate_channels(channels as isize) as usize;
+        let mixer = Mixer {
+            channels: channels,
+            reserved: Cell::new(0),
+            master: Cell::new(1.0),
+            music: Cell::new(1.0),
+            sfx: Cell::new(1.0),
+            paused: Cell::new(false),
+        };
+        mixer.apply_volumes();
+        Ok(mixer)
+    }
+
+    /// Returns the number of channels sound effects are mixed on.
+    #[inline]
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Reserves the first `count` channels, so that `play_sound` never
+    /// uses them, and returns the number of channels reserved.
+    ///
+    /// Reserved channels can still be played on with `play_sound_on`,
+    /// e.g. for dialogue that must not be cut off by other sounds.
+    pub fn reserve_channels(&self, count: usize) -> usize {
+        // SDL_mixer may reserve fewer channels than asked for
+        let reserved = sdl2_mixer::reserve_channels(count.min(self.channels) as isize) as usize;
+        self.reserved.set(reserved);
+        reserved
+    }
+
+    /// Returns the number of reserved channels.
+    #[inline]
+    pub fn reserved_channels(&self) -> usize {
+        self.reserved.get()
+    }
+
+    /// Returns the number of channels currently playing a sound.
+    pub fn playing_channels(&self) -> usize {
+        (0..self.channels).filter(|&channel| self.sound_channel(channel).is_playing()).count()
+    }
+
+    /// Plays a sound effect on the first free unreserved channel, and
+    /// returns the channel.
+    ///
+    /// Returns `None` without playing the sound if every unreserved
+    /// channel is busy.
+    pub fn play_sound(&self, sound: &Chunk) -> Result<Option<Channel>, String> {
+        match Channel::all().play(sound, 0) {
+            Ok(channel) => self.start(channel).map(Some),
+            Err(_) if self.free_channels() == 0 => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the number of unreserved channels not playing a sound.
+    pub fn free_channels(&self) -> usize {
+        (self.reserved.get()..self.channels)
+            .filter(|&channel| !self.sound_channel(channel).is_playing())
+            .count()
+    }
+
+    /// Plays a sound effect on the given channel, cutting off what it
+    /// was playing.
+    pub fn play_sound_on(&self, channel: usize, sound: &Chunk) -> Result<Channel, String> {
+        if channel >= self.channels {
+            return Err(format!("no such channel: {}", channel));
+        }
+        self.start(self.sound_channel(channel).play(sound, 0)?)
+    }
+
+    /// Stops all sound effects.
+    pub fn stop_sounds(&self) {
+        for channel in 0..self.channels {
+            self.sound_channel(channel).halt();
+        }
+    }
+
+    /// Plays a music track, replacing the current one.
+    ///
+    /// The track is played `loops` times, or forever if that is `None`.
+    pub fn play_music(&self, music: &Music, loops: Option<u32>) -> Result<(), String> {
+        music.play(loop_count(loops))?;
+        if self.paused.get() {
+            Music::pause();
+        }
+        Ok(())
+    }
+
+    /// Plays a music track like `play_music`, fading it in over
+    /// `fade`.
+    pub fn fade_in_music(&self,
+                         music: &Music,
+                         loops: Option<u32>,
+                         fade: Duration)
+                         -> Result<(), String> {
+        music.fade_in(loop_count(loops), fade.as_millis() as isize)?;
+        if self.paused.get() {
+            Music::pause();
+        }
+        Ok(())
+    }
+
+    /// Plays a music track like `fade_in_music`, starting at `position`
+    /// into the track.
+    pub fn fade_in_music_from(&self,
+                              music: &Music,
+                              loops: Option<u32>,
+                              fade: Duration,
+                              position: Duration)
+                              -> Result<(), String> {
+        music.fade_in_from_pos(loop_count(loops),
+                               fade.as_millis() as isize,
+                               position.as_secs_f64())?;
+        if self.paused.get() {
+            Music::pause();
+        }
+        Ok(())
+    }
+
+    /// Stops the music.
+    pub fn stop_music(&self) {
+        Music::halt();
+    }
+
+    /// Fades the music out over `fade` and stops it.
+    pub fn fade_out_music(&self, fade: Duration) -> Result<(), String> {
+        Music::fade_out(fade.as_millis() as isize)
+    }
+
+    /// Returns whether a music track is playing, even if paused.
+    pub fn is_music_playing(&self) -> bool {
+        Music::is_playing()
+    }
+
+    /// Returns the volume of a group, between `0.0` and `1.0`.
+    pub fn volume(&self, group: VolumeGroup) -> f32 {
+        self.group(group).get()
+    }
+
+    /// Sets the volume of a group, between `0.0` and `1.0`.
+    ///
+    /// The volume of the music and sound effects is multiplied by the
+    /// master volume.
+    pub fn set_volume(&self, group: VolumeGroup, volume: f32) {
+        self.group(group).set(volume.clamp(0.0, 1.0));
+        self.apply_volumes();
+    }
+
+    /// Pauses all sound effects and the music.
+    ///
+    /// Sounds that are played while paused start out paused.
+    pub fn pause(&self) {
+        self.paused.set(true);
+        Channel::all().pause();
+        Music::pause();
+    }
+
+    /// Resumes the sound effects and music after a `pause`.
+    pub fn resume(&self) {
+        self.paused.set(false);
+        Channel::all().resume();
+        Music::resume();
+    }
+
+    /// Returns whether the mixer is paused.
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    fn sound_channel(&self, channel: usize) -> Channel {
+        sdl2_mixer::channel(channel as isize)
+    }
+
+    fn start(&self, channel: Channel) -> Result<Channel, String> {
+        if self.paused.get() {
+            channel.pause();
+        }
+        Ok(channel)
+    }
+
+    fn group(&self, group: VolumeGroup) -> &Cell<f32> {
+        match group {
+            VolumeGroup::Master => &self.master,
+            VolumeGroup::Music => &self.music,
+            VolumeGroup::Sfx => &self.sfx,
+        }
+    }
+
+    fn apply_volumes(&self) {
+        let master = self.master.get();
+        Channel::all().set_volume(mixer_volume(master * self.sfx.get()));
+        Music::set_volume(mixer_volume(master * self.music.get()));
+    }
+}
+
+impl Drop for Mixer {
+    fn drop(&mut self) {
+        sdl2_mixer::close_audio();
+    }
+}
+
+#[inline]
+fn mixer_volume(volume: f32) -> isize {
+    (volume * MAX_VOLUME as f32).round() as isize
+}
+
+#[inline]
+fn loop_count(loops: Option<u32>) -> isize {
+    loops.map_or(-1, |loops| loops as isize)
+}
+
+/// A request to the audio system, sent as part of a message.
+///
+/// Sounds and music are referred to by their path in the
+/// `ResourceManager`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AudioCommand {
+    /// Plays a sound effect once.
+    PlaySound(Cow<'static, str>),
+    /// Changes the background music.
+    Music(MusicCommand),
+    /// Pauses all audio.
+    Pause,
+    /// Resumes all audio.
+    Resume,
+    /// Sets the volume of a group.
+    SetVolume(VolumeGroup, f32),
+}
+
+/// A message that may carry an `AudioCommand`.
+pub trait AudioMessage {
+    /// Returns the audio command in this message, if any.
+    fn audio_command(&self) -> Option<&AudioCommand>;
+}
+
+impl AudioMessage for AudioCommand {
+    fn audio_command(&self) -> Option<&AudioCommand> {
+        Some(self)
+    }
+}
+
+/// A failure to run an audio command.
+#[derive(Clone, Debug)]
+pub struct AudioError {
+    /// The command that failed, or `None` if the background music
+    /// could not move on to the next track of its playlist.
+    pub command: Option<AudioCommand>,
+    /// The reason the command failed.
+    pub message: String,
+}
+
+impl Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.command {
+            Some(ref command) => write!(f, "could not run {:?}: {}", command, self.message),
+            None => write!(f, "could not play the next track: {}", self.message),
+        }
+    }
+}
+
+/// A plugin playing the audio commands in messages.
+///
+/// Commands are run when their message is handled. Sounds that find
+/// no free channel are dropped, and other failures are written to
+/// stderr by default.
+///
+/// The player plays the background music on the music stream of the
+/// mixer, and should be the only one using it.
+pub struct AudioPlayer<'m, 'a: 'm, 'r: 'a> {
+    mixer: &'m Mixer,
+    resources: &'m ResourceManager<'a, 'r>,
+    jukebox: Jukebox,
+    on_error: Box<FnMut(&AudioError) + 'm>,
+}
+
+impl<'m, 'a, 'r> AudioPlayer<'m, 'a, 'r> {
+    /// Creates a plugin playing on `mixer`, with sounds and music
+    /// loaded from `resources`.
+    pub fn new(mixer: &'m Mixer, resources: &'m ResourceManager<'a, 'r>) -> Self {
+        AudioPlayer {
+            mixer: mixer,
+            resources: resources,
+            jukebox: Jukebox::new(),
+            on_error: Box::new(|err| {
+                let _ = writeln!(io::stderr(), "{}", err);
+            }),
+        }
+    }
+
+    /// Sets the function failures to run commands are reported to.
+    pub fn set_error_handler<F>(&mut self, on_error: F)
+        where F: FnMut(&AudioError) + 'm
+    {
+        self.on_error = Box::new(on_error);
+    }
+
+    /// Returns the background music that is playing or being faded to.
+    pub fn current_track(&self) -> Option<&Track> {
+        self.jukebox.current()
+    }
+
+    /// Runs an audio command.
+    ///
+    /// Playing a sound when every channel is busy is not an error, and
+    /// does nothing.
+    pub fn run(&mut self, command: &AudioCommand) -> Result<(), String> {
+        match *command {
+            AudioCommand::PlaySound(ref path) => {
+                self.mixer.play_sound(&*self.resources.try_sound(path)?)?;
+            }
+            AudioCommand::Music(ref command) => {
+                self.jukebox.run(command, self.mixer, self.resources)?;
+            }
+            AudioCommand::Pause => self.mixer.pause(),
+            AudioCommand::Resume => self.mixer.resume(),
+            AudioCommand::SetVolume(group, volume) => self.mixer.set_volume(group, volume),
+        }
+        Ok(())
+    }
+}
+
+impl<'m, 'a, 'r, M> Plugin<M> for AudioPlayer<'m, 'a, 'r>
+    where M: AudioMessage
+{
+    fn message(&mut self, _frame: &Frame, message: &M) {
+        if let Some(command) = message.audio_command() {
+            if let Err(message) = self.run(command) {
+                (self.on_error)(&AudioError {
+                    command: Some(command.clone()),
+                    message: message,
+                });
+            }
+        }
+    }
+
+    fn after_messages(&mut self, _frame: &Frame) {
+        if let Err(message) = self.jukebox.poll(self.mixer, self.resources) {
+            (self.on_error)(&AudioError {
+                command: None,
+                message: message,
+            });
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use frame::Frame;
+use gameobject::Behavior;
+use queue::Queue;
+use renderer::Renderer;
+
+/// A message that can be sorted into kinds for a `Bus`.
+///
+/// For enums, `Kind` is usually either a field-less mirror of the enum
+/// or `std::mem::Discriminant<Self>`.
+pub trait MessageKind {
+    /// The type identifying each kind of message.
+    type Kind: Copy + Eq + Hash;
+
+    /// Returns the kind of the message.
+    fn kind(&self) -> Self::Kind;
+}
+
+/// Identifies a subscriber of a `Bus`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SubscriberId(usize);
+
+/// Statistics about one kind of message on a `Bus`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KindStats {
+    /// The number of distinct subscribers to the kind, including those
+    /// that subscribe to every kind.
+    pub subscribers: usize,
+    /// The number of messages of this kind handled by the bus.
+    pub messages: u64,
+    /// The number of times a message of this kind was passed on to a
+    /// subscriber.
+    pub deliveries: u64,
+}
+
+/// A collection of behaviors that only receive the kinds of messages
+/// they subscribe to.
+///
+/// All subscribers are updated and rendered in the order they were
+/// added, but messages are only passed on to the subscribers of their
+/// kind, by reference, which saves the dispatch to subscribers that do
+/// not need them.
+pub struct Bus<K, B> {
+    subscribers: Vec<B>,
+    subscriptions: HashMap<K, Vec<usize>>,
+    wildcards: Vec<usize>,
+    /// The subscribers to every kind that unsubscribed from a kind.
+    exclusions: HashMap<K, Vec<usize>>,
+    stats: HashMap<K, KindStats>,
+    targets: Vec<usize>,
+}
+
+impl<K, B> Bus<K, B>
+    where K: Copy + Eq + Hash
+{
+    /// Creates a bus without subscribers.
+    pub fn new() -> Bus<K, B> {
+        Bus {
+            subscribers: Vec::new(),
+            subscriptions: HashMap::new(),
+            wildcards: Vec::new(),
+            exclusions: HashMap::new(),
+            stats: HashMap::new(),
+            targets: Vec::new(),
+        }
+    }
+
+    /// Adds a subscriber receiving the given kinds of messages.
+    pub fn subscribe(&mut self, behavior: B, kinds: &[K]) -> SubscriberId {
+        let id = SubscriberId(self.subscribers.len());
+        self.subscribers.push(behavior);
+        for &kind in kinds {
+            self.subscribe_to(id, kind);
+        }
+        id
+    }
+
+    /// Adds a subscriber receiving every message.
+    ///
+    /// The subscriber can still unsubscribe from single kinds.
+    pub fn subscribe_all(&mut self, behavior: B) -> SubscriberId {
+        let id = self.subscribe(behavior, &[]);
+        self.wildcards.push(id.0);
+        id
+    }
+
+    /// Subscribes an existing subscriber to another kind of message.
+    ///
+    /// Returns `false` if there is no such subscriber, e.g. as the id
+    /// belongs to another bus.
+    pub fn subscribe_to(&mut self, id: SubscriberId, kind: K) -> bool {
+        if id.0 >= self.subscribers.len() {
+            return false;
+        }
+        if self.is_wildcard(id) {
+            remove_sorted(self.exclusions.entry(kind).or_default(), id.0);
+        } else {
+            insert_sorted(self.subscriptions.entry(kind).or_default(), id.0);
+        }
+        true
+    }
+
+    /// Unsubscribes a subscriber from a kind of message.
+    ///
+    /// Returns `false` if there is no such subscriber.
+    pub fn unsubscribe(&mut self, id: SubscriberId, kind: K) -> bool {
+        if id.0 >= self.subscribers.len() {
+            return false;
+        }
+        if self.is_wildcard(id) {
+            insert_sorted(self.exclusions.entry(kind).or_default(), id.0);
+        } else {
+            remove_sorted(self.subscriptions.entry(kind).or_default(), id.0);
+        }
+        true
+    }
+
+    fn is_wildcard(&self, id: SubscriberId) -> bool {
+        // Ids are handed out in increasing order
+        self.wildcards.binary_search(&id.0).is_ok()
+    }
+
+    /// Returns a subscriber.
+    pub fn get(&self, id: SubscriberId) -> Option<&B> {
+        self.subscribers.get(id.0)
+    }
+
+    /// Returns a subscriber.
+    pub fn get_mut(&mut self, id: SubscriberId) -> Option<&mut B> {
+        self.subscribers.get_mut(id.0)
+    }
+
+    /// Returns the number of subscribers.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    /// Returns whether there are no subscribers.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    /// Returns the number of subscribers to a kind of message.
+    ///
+    /// Subscribers to every kind are included, but each subscriber is
+    /// only counted once.
+    pub fn subscriber_count(&self, kind: K) -> usize {
+        let specific = self.subscriptions.get(&kind).map_or(0, |s| s.len());
+        let excluded = self.exclusions.get(&kind).map_or(0, |s| s.len());
+        specific + self.wildcards.len() - excluded
+    }
+
+    /// Returns the statistics for a kind of message.
+    pub fn stats(&self, kind: K) -> KindStats {
+        KindStats { subscribers: self.subscriber_count(kind), ..self.stats_entry(kind) }
+    }
+
+    /// Returns the statistics for every kind of message that has been
+    /// subscribed to or handled.
+    pub fn all_stats(&self) -> Vec<(K, KindStats)> {
+        let mut kinds: Vec<K> = self.subscriptions.keys().cloned().collect();
+        kinds.extend(self.stats.keys().filter(|k| !self.subscriptions.contains_key(k)));
+        kinds.into_iter().map(|kind| (kind, self.stats(kind))).collect()
+    }
+
+    /// Resets the message and delivery counts.
+    pub fn reset_stats(&mut self) {
+        self.stats.clear();
+    }
+
+    fn stats_entry(&self, kind: K) -> KindStats {
+        self.stats.get(&kind).cloned().unwrap_or_default()
+    }
+
+    /// Collects the subscribers of a message in `targets`, and counts
+    /// the message.
+    fn find_targets<M>(&mut self, message: &M)
+        where M: MessageKind<Kind = K>
+    {
+        let kind = message.kind();
+
+        // Merge the specific and wildcard subscribers in order
+        self.targets.clear();
+        if let Some(subscribers) = self.subscriptions.get(&kind) {
+            self.targets.extend(subscribers);
+        }
+        let excluded = self.exclusions.get(&kind).map_or(&[][..], |s| &s[..]);
+        self.targets.extend(self.wildcards.iter().filter(|i| excluded.binary_search(i).is_err()));
+        self.targets.sort();
+
+        let stats = self.stats.entry(kind).or_default();
+        stats.messages += 1;
+        stats.deliveries += self.targets.len() as u64;
+    }
+}
+
+/// Inserts `value` into a sorted vector, unless it is already there.
+fn insert_sorted(values: &mut Vec<usize>, value: usize) {
+    if let Err(index) = values.binary_search(&value) {
+        values.insert(index, value);
+    }
+}
+
+/// Removes `value` from a sorted vector.
+fn remove_sorted(values: &mut Vec<usize>, value: usize) {
+    if let Ok(index) = values.binary_search(&value) {
+        values.remove(index);
+    }
+}
+
+impl<K, B> Default for Bus<K, B>
+    where K: Copy + Eq + Hash
+{
+    fn default() -> Bus<K, B> {
+        Bus::new()
+    }
+}
+
+impl<S, K, B> Behavior<S> for Bus<K, B>
+    where K: Copy + Eq + Hash,
+          B: Behavior<S>,
+          B::Message: MessageKind<Kind = K>
+{
+    type Message = B::Message;
+
+    fn initialize(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
+        for subscriber in &mut self.subscribers {
+            subscriber.initialize(state, frame, queue);
+        }
+    }
+
+    fn update(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
+        for subscriber in &mut self.subscribers {
+            subscriber.update(state, frame, queue);
+        }
+    }
+
+    fn handle_ref(&mut self,
+                  state: &mut S,
+                  frame: &Frame,
+                  message: &Self::Message,
+                  queue: &mut Queue<Self::Message>) {
+        self.find_targets(message);
+        for &index in &self.targets {
+            self.subscribers[index].handle_ref(state, frame, message, queue);
+        }
+    }
+
+    fn render(&mut self, state: &S, frame: &Frame, renderer: &mut Renderer) {
+        for subscriber in &mut self.subscribers {
+            subscriber.render(state, frame, renderer);
+        }
+    }
+
+    fn render_window(&mut self, state: &S, frame: &Frame, window_id: u32, renderer: &mut Renderer) {
+        for subscriber in &mut self.subscribers {
+            subscriber.render_window(state, frame, window_id, renderer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bus, MessageKind};
+    use frame::Frame;
+    use gameobject::Behavior;
+    use queue::{Queue, Scheduler};
+
+    impl MessageKind for u32 {
+        type Kind = u32;
+
+        fn kind(&self) -> u32 {
+            *self
+        }
+    }
+
+    /// Records its name and the messages it gets in the state.
+    struct Named(&'static str);
+
+    impl Behavior<Vec<(&'static str, u32)>> for Named {
+        type Message = u32;
+
+        fn handle_ref(&mut self,
+                      state: &mut Vec<(&'static str, u32)>,
+                      _frame: &Frame,
+                      message: &u32,
+                      _queue: &mut Queue<u32>) {
+            state.push((self.0, *message));
+        }
+    }
+
+    fn send(bus: &mut Bus<u32, Named>, message: u32) -> Vec<(&'static str, u32)> {
+        let mut state = Vec::new();
+        let mut messages = Vec::new();
+        let mut scheduler = Scheduler::new();
+        bus.handle(&mut state,
+                   &Frame::new(),
+                   message,
+                   &mut Queue::new(&mut messages, &mut scheduler));
+        state
+    }
+
+    #[test]
+    fn subscribers_are_counted_once() {
+        let mut bus = Bus::new();
+        let both = bus.subscribe_all(());
+        bus.subscribe_to(both, 1);
+        bus.subscribe((), &[1, 2]);
+        bus.subscribe((), &[2]);
+
+        assert_eq!(bus.subscriber_count(1), 2);
+        assert_eq!(bus.subscriber_count(2), 3);
+        assert_eq!(bus.subscriber_count(3), 1);
+        assert_eq!(bus.stats(1).subscribers, 2);
+    }
+
+    #[test]
+    fn messages_reach_each_subscriber_once() {
+        let mut bus = Bus::new();
+        let all = bus.subscribe_all(Named("all"));
+        bus.subscribe_to(all, 1);
+        bus.subscribe(Named("one"), &[1, 1]);
+        bus.subscribe(Named("two"), &[2]);
+
+        assert_eq!(send(&mut bus, 1), vec![("all", 1), ("one", 1)]);
+        assert_eq!(send(&mut bus, 2), vec![("all", 2), ("two", 2)]);
+        assert_eq!(send(&mut bus, 3), vec![("all", 3)]);
+        assert_eq!(bus.stats(1).deliveries, 2);
+    }
+
+    #[test]
+    fn unsubscribed_kinds_are_not_delivered() {
+        let mut bus = Bus::new();
+        let all = bus.subscribe_all(Named("all"));
+        let one = bus.subscribe(Named("one"), &[1]);
+        assert!(bus.unsubscribe(all, 1));
+        assert!(bus.unsubscribe(one, 1));
+
+        assert!(send(&mut bus, 1).is_empty());
+        assert_eq!(send(&mut bus, 2), vec![("all", 2)]);
+        assert_eq!(bus.subscriber_count(1), 0);
+
+        assert!(bus.subscribe_to(all, 1));
+        assert_eq!(send(&mut bus, 1), vec![("all", 1)]);
+    }
+
+    #[test]
+    fn ids_of_other_buses_are_rejected() {
+        let mut other: Bus<u32, Named> = Bus::new();
+        other.subscribe(Named("other"), &[]);
+        let foreign = other.subscribe(Named("foreign"), &[]);
+
+        let mut bus = Bus::new();
+        bus.subscribe(Named("one"), &[1]);
+        assert!(!bus.subscribe_to(foreign, 1));
+        assert!(!bus.unsubscribe(foreign, 1));
+        assert_eq!(send(&mut bus, 1), vec![("one", 1)]);
+    }
+}
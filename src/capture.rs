@@ -0,0 +1,154 @@
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+
+use frame::Frame;
+use renderer::Renderer;
+
+/// A failure to save a captured frame.
+#[derive(Clone, Debug)]
+pub struct CaptureError {
+    /// The path the image was saved to.
+    pub path: PathBuf,
+    /// The reason the image could not be saved.
+    pub message: String,
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "could not save {}: {}", self.path.display(), self.message)
+    }
+}
+
+/// Screenshots and image sequences of a `Game`.
+///
+/// Screenshots are taken when the hotkey is pressed, F12 by default,
+/// and saved as `screenshot-<frame>.png`. In sequence mode, every Nth
+/// frame is saved as `frame-<index>.png`, numbered from zero without
+/// gaps, so that the images can be turned into a video.
+///
+/// Frames are captured right after the behavior is rendered, so
+/// overlays like the profiler are left out. All images are written to
+/// a single directory, and failures are written to stderr by default.
+///
+/// Enable it with `Game::set_capture`.
+pub struct Capture<'a> {
+    dir: PathBuf,
+    hotkey: Option<Keycode>,
+    requested: bool,
+    every: Option<u32>,
+    sequence_index: u64,
+    on_error: Box<FnMut(&CaptureError) + 'a>,
+}
+
+impl<'a> Capture<'a> {
+    /// Creates a capture saving images to `dir`.
+    ///
+    /// The directory is created when the first image is saved.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Capture<'a> {
+        Capture {
+            dir: dir.into(),
+            hotkey: Some(Keycode::F12),
+            requested: false,
+            every: None,
+            sequence_index: 0,
+            on_error: Box::new(|err| {
+                let _ = writeln!(io::stderr(), "{}", err);
+            }),
+        }
+    }
+
+    /// Sets the key that takes a screenshot, or disables it.
+    pub fn set_hotkey(&mut self, hotkey: Option<Keycode>) {
+        self.hotkey = hotkey;
+    }
+
+    /// Sets the function failures to save images are reported to.
+    pub fn set_error_handler<F>(&mut self, on_error: F)
+        where F: FnMut(&CaptureError) + 'a
+    {
+        self.on_error = Box::new(on_error);
+    }
+
+    /// Takes a screenshot of the next rendered frame.
+    pub fn request_screenshot(&mut self) {
+        self.requested = true;
+    }
+
+    /// Starts or stops saving every `nth` rendered frame.
+    ///
+    /// Starting a new sequence overwrites the images of the previous
+    /// one.
+    pub fn set_sequence(&mut self, nth: Option<u32>) {
+        self.every = nth.map(|nth| nth.max(1));
+        self.sequence_index = 0;
+    }
+
+    /// Returns whether an image sequence is being saved.
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.every.is_some()
+    }
+
+    /// Handles the event if it is the screenshot hotkey.
+    ///
+    /// Returns `true` if the event was consumed.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        let (key, pressed) = match *event {
+            Event::KeyDown { keycode: Some(key), repeat, .. } => (key, !repeat),
+            Event::KeyUp { keycode: Some(key), .. } => (key, false),
+            _ => return false,
+        };
+        if Some(key) != self.hotkey {
+            return false;
+        }
+        if pressed {
+            self.requested = true;
+        }
+        true
+    }
+
+    /// Saves the images due for a rendered frame.
+    pub fn capture(&mut self, frame: &Frame, renderer: &Renderer) {
+        if self.requested {
+            self.requested = false;
+            self.save(format!("screenshot-{:06}.png", frame.number), renderer);
+        }
+        if let Some(every) = self.every {
+            if frame.number.is_multiple_of(every as u64) {
+                let name = format!("frame-{:06}.png", self.sequence_index);
+                self.save(name, renderer);
+                self.sequence_index += 1;
+            }
+        }
+    }
+
+    fn save(&mut self, name: String, renderer: &Renderer) {
+        let path = self.dir.join(name);
+        let result = fs::create_dir_all(&self.dir)
+            .map_err(|err| err.to_string())
+            .and_then(|_| renderer.screenshot(&path));
+        if let Err(message) = result {
+            (self.on_error)(&CaptureError {
+                path: path,
+                message: message,
+            });
+        }
+    }
+}
+
+impl<'a> Debug for Capture<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Capture")
+            .field("dir", &self.dir)
+            .field("hotkey", &self.hotkey)
+            .field("requested", &self.requested)
+            .field("every", &self.every)
+            .field("sequence_index", &self.sequence_index)
+            .finish()
+    }
+}
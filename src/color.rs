@@ -1,6 +1,7 @@
 use sdl2::pixels::Color as SdlColor;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub struct Color(pub u8, pub u8, pub u8, pub u8);
 
 impl Color {
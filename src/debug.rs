@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+
+use capture::Capture;
+use event::EventSource;
+
+const MIN_TIME_SCALE: f64 = 1.0 / 16.0;
+const MAX_TIME_SCALE: f64 = 16.0;
+
+/// Hotkeys for pausing, stepping and scaling the time of a game.
+///
+/// When enabled on a `Game`, the key presses of these controls are
+/// consumed before they reach the `InputManager`, so they never show up
+/// as game messages.
+///
+/// While paused, the game is still rendered and messages are still
+/// handled, so that the game can be quit, but `Behavior::update` is
+/// only called when stepping.
+#[derive(Clone, Debug)]
+pub struct DebugControls {
+    pause_key: Keycode,
+    step_key: Keycode,
+    slower_key: Keycode,
+    faster_key: Keycode,
+    paused: bool,
+    steps: u32,
+    time_scale: f64,
+}
+
+impl DebugControls {
+    /// Creates debug controls with the default keys.
+    ///
+    /// F5 toggles pause, F6 advances a single tick while paused, and F7
+    /// and F8 halve and double the time scale respectively.
+    pub fn new() -> DebugControls {
+        DebugControls::with_keys(Keycode::F5, Keycode::F6, Keycode::F7, Keycode::F8)
+    }
+
+    /// Creates debug controls with the given keys.
+    pub fn with_keys(pause_key: Keycode,
+                     step_key: Keycode,
+                     slower_key: Keycode,
+                     faster_key: Keycode)
+                     -> DebugControls {
+        DebugControls {
+            pause_key: pause_key,
+            step_key: step_key,
+            slower_key: slower_key,
+            faster_key: faster_key,
+            paused: false,
+            steps: 0,
+            time_scale: 1.0,
+        }
+    }
+
+    /// Returns whether the simulation is paused.
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Pauses or resumes the simulation.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.steps = 0;
+    }
+
+    /// Requests a single tick to be run while paused.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.steps += 1;
+        }
+    }
+
+    /// Returns the factor game time is scaled by.
+    #[inline]
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Sets the factor game time is scaled by.
+    ///
+    /// The factor is clamped to the range from 1/16 to 16.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+    }
+
+    /// Scales a real duration by the time scale.
+    pub fn scale(&self, duration: Duration) -> Duration {
+        Duration::from_nanos((duration.as_nanos() as f64 * self.time_scale) as u64)
+    }
+
+    /// Takes a pending single step, if any.
+    pub fn take_step(&mut self) -> bool {
+        if self.steps > 0 {
+            self.steps -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Handles the event if it is a debug hotkey.
+    ///
+    /// Returns `true` if the event was consumed.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        let (key, pressed) = match *event {
+            Event::KeyDown { keycode: Some(key), repeat, .. } => (key, !repeat),
+            Event::KeyUp { keycode: Some(key), .. } => (key, false),
+            _ => return false,
+        };
+        if key == self.pause_key {
+            if pressed {
+                let paused = !self.paused;
+                self.set_paused(paused);
+            }
+        } else if key == self.step_key {
+            if pressed {
+                self.step();
+            }
+        } else if key == self.slower_key {
+            if pressed {
+                let time_scale = self.time_scale / 2.0;
+                self.set_time_scale(time_scale);
+            }
+        } else if key == self.faster_key {
+            if pressed {
+                let time_scale = self.time_scale * 2.0;
+                self.set_time_scale(time_scale);
+            }
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+impl Default for DebugControls {
+    fn default() -> DebugControls {
+        DebugControls::new()
+    }
+}
+
+/// An event source passing debug and screenshot hotkeys to the debug
+/// controls and capture, if any, and all other events on.
+pub struct Intercept<'a, 'c: 'a, E: ?Sized + 'a> {
+    controls: Option<&'a mut DebugControls>,
+    capture: Option<&'a mut Capture<'c>>,
+    events: &'a mut E,
+}
+
+impl<'a, 'c, E: ?Sized> Intercept<'a, 'c, E> {
+    /// Creates a new event source intercepting the hotkeys of `events`.
+    pub fn new(controls: Option<&'a mut DebugControls>,
+               capture: Option<&'a mut Capture<'c>>,
+               events: &'a mut E)
+               -> Intercept<'a, 'c, E> {
+        Intercept {
+            controls: controls,
+            capture: capture,
+            events: events,
+        }
+    }
+}
+
+impl<'a, 'c, E> EventSource for Intercept<'a, 'c, E>
+    where E: EventSource + ?Sized
+{
+    fn poll_event(&mut self, tick: u64) -> Option<Event> {
+        while let Some(event) = self.events.poll_event(tick) {
+            if let Some(ref mut controls) = self.controls {
+                if controls.handle_event(&event) {
+                    continue;
+                }
+            }
+            if let Some(ref mut capture) = self.capture {
+                if capture.handle_event(&event) {
+                    continue;
+                }
+            }
+            return Some(event);
+        }
+        None
+    }
+}
@@ -7,13 +7,18 @@ use ref_filter_map::{ref_filter_map, ref_mut_filter_map};
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::render::{Renderer as SdlRenderer, RendererInfo, Texture, TextureAccess,
                    TextureValueError};
-use sdl2::surface::SurfaceRef;
+use sdl2::surface::{Surface, SurfaceRef};
 use sdl2::video::WindowRef;
 use sdl2_image::LoadTexture;
 
 use renderer::{create_renderer, Renderer};
 use rect::Rect;
 
+#[inline]
+pub fn release_renderer(device: &Device) {
+    device.renderer_created.set(false);
+}
+
 pub struct Device<'r> {
     inner: RefCell<SdlRenderer<'r>>,
     renderer_created: Cell<bool>,
@@ -28,14 +33,29 @@ impl<'r> Device<'r> {
         }
     }
 
+    /// Creates a device rendering to an offscreen surface in software.
+    ///
+    /// This needs no window, which makes it suitable for tests.
+    pub fn software(width: u32, height: u32) -> Result<Device<'static>, String> {
+        let surface = Surface::new(width, height, PixelFormatEnum::RGBA8888)?;
+        let renderer = SdlRenderer::from_surface(surface)?;
+        Ok(Device::new(renderer))
+    }
+
     #[inline]
     pub fn create_renderer<'a>(&'a self) -> Renderer<'a, 'r> {
         assert!(!self.renderer_created.get(),
-                "renderer already created for this device");
+                "renderer already exists for this device");
         self.renderer_created.set(true);
         create_renderer(self)
     }
 
+    /// Returns whether a renderer currently exists for this device.
+    #[inline]
+    pub fn has_renderer(&self) -> bool {
+        self.renderer_created.get()
+    }
+
     #[inline]
     pub fn borrow_mut(&self) -> RefMut<SdlRenderer<'r>> {
         self.inner.borrow_mut()
@@ -56,6 +76,12 @@ impl<'r> Device<'r> {
         self.inner.borrow().info()
     }
 
+    /// Returns the id of the window rendered to, if any.
+    #[inline]
+    pub fn window_id(&self) -> Option<u32> {
+        self.borrow_window().map(|w| w.id())
+    }
+
     #[inline]
     pub fn borrow_window(&self) -> Option<Ref<WindowRef>> {
         ref_filter_map(self.borrow(), |r| r.window())
@@ -0,0 +1,432 @@
+use std::io::{self, Write};
+
+use frame::Frame;
+use gameobject::Behavior;
+use queue::Queue;
+use renderer::Renderer;
+
+/// Identifies an entity in `Entities` or a `Router`.
+///
+/// The slot of a removed entity may be taken by a new one, but with a
+/// new generation, so the ids of removed entities simply no longer
+/// match anything.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// A collection of values keyed by generational `Entity` ids, kept in
+/// the order they were inserted.
+///
+/// This is shared by the entity collections of the crate.
+pub struct EntityMap<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    order: Vec<u32>,
+}
+
+impl<T> EntityMap<T> {
+    /// Creates an empty map.
+    pub fn new() -> EntityMap<T> {
+        EntityMap {
+            slots: Vec::new(),
+            free: Vec::new(),
+            order: Vec::new(),
+        }
+    }
+
+    /// Inserts a value created from its id, and returns the id.
+    pub fn insert_with<F>(&mut self, create: F) -> Entity
+        where F: FnOnce(Entity) -> T
+    {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: None,
+                });
+                self.slots.len() as u32 - 1
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        let entity = Entity {
+            index: index,
+            generation: slot.generation,
+        };
+        slot.value = Some(create(entity));
+        self.order.push(index);
+        entity
+    }
+
+    /// Removes a value and returns it.
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        if !self.contains(entity) {
+            return None;
+        }
+        let slot = &mut self.slots[entity.index as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(entity.index);
+        self.order.retain(|&index| index != entity.index);
+        slot.value.take()
+    }
+
+    /// Returns whether there is a value for an id.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.slots.get(entity.index as usize).is_some_and(|slot| {
+            slot.generation == entity.generation && slot.value.is_some()
+        })
+    }
+
+    /// Returns a value.
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        if !self.contains(entity) {
+            return None;
+        }
+        self.slots[entity.index as usize].value.as_ref()
+    }
+
+    /// Returns a value.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        if !self.contains(entity) {
+            return None;
+        }
+        self.slots[entity.index as usize].value.as_mut()
+    }
+
+    /// Returns the number of values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Returns whether there are no values.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Returns the ids of all values, in the order they were inserted.
+    pub fn ids(&self) -> Vec<Entity> {
+        self.order
+            .iter()
+            .map(|&index| {
+                Entity {
+                    index: index,
+                    generation: self.slots[index as usize].generation,
+                }
+            })
+            .collect()
+    }
+
+    /// Calls `f` with each value, in the order they were inserted.
+    pub fn each_mut<F>(&mut self, mut f: F)
+        where F: FnMut(&mut T)
+    {
+        for &index in &self.order {
+            f(self.slots[index as usize].value.as_mut().expect("inserted entity has no value"));
+        }
+    }
+}
+
+impl<T> Default for EntityMap<T> {
+    fn default() -> EntityMap<T> {
+        EntityMap::new()
+    }
+}
+
+/// Creates the behavior of a spawned entity from its id.
+pub type Spawner<S, M> = Box<FnOnce(Entity) -> Box<Behavior<S, Message = M>>>;
+
+/// A change to the entities of `Entities`.
+pub enum EntityCommand<S, M> {
+    /// Adds an entity created from its id.
+    Spawn(Spawner<S, M>),
+    /// Removes an entity.
+    Despawn(Entity),
+}
+
+impl<S, M> EntityCommand<S, M> {
+    /// Creates a command that spawns the entity created by `create`.
+    ///
+    /// `create` is given the id of the new entity, e.g. so that it can
+    /// despawn itself later.
+    pub fn spawn<F>(create: F) -> EntityCommand<S, M>
+        where F: FnOnce(Entity) -> Box<Behavior<S, Message = M>> + 'static
+    {
+        EntityCommand::Spawn(Box::new(create))
+    }
+}
+
+/// A message that may carry an `EntityCommand`.
+pub trait EntityMessage<S>: Sized {
+    /// Returns the command in this message, or the message itself, if
+    /// it is not a command.
+    fn into_command(self) -> Result<EntityCommand<S, Self>, Self>;
+
+    /// Returns the command in this message, if it is one.
+    fn command(&self) -> Option<&EntityCommand<S, Self>>;
+}
+
+/// An owned collection of entities that can be spawned and despawned
+/// while the game is running.
+///
+/// Commands sent as messages are applied as soon as they are handled,
+/// so a despawned entity gets no further messages, and a spawned one is
+/// initialized right away and gets the messages after the command.
+/// Other messages are passed by reference to every entity, so they do
+/// not need to implement `Clone`, and entities are updated and rendered
+/// in the order they were spawned.
+///
+/// As spawn commands carry the new entities, the collection has to own
+/// them, so spawns are only applied when they are passed to `handle`.
+/// Spawns passed to `handle_ref` are reported on stderr and ignored,
+/// like scene transitions in a `SceneStack`.
+pub struct Entities<S, M> {
+    entities: EntityMap<Box<Behavior<S, Message = M>>>,
+}
+
+impl<S, M> Entities<S, M> {
+    /// Creates an empty collection.
+    pub fn new() -> Entities<S, M> {
+        Entities { entities: EntityMap::new() }
+    }
+
+    /// Adds an entity right away and returns its id.
+    ///
+    /// The entity is not initialized, except along with the collection.
+    pub fn spawn(&mut self, behavior: Box<Behavior<S, Message = M>>) -> Entity {
+        self.spawn_with(|_| behavior)
+    }
+
+    /// Adds an entity created from its id right away, and returns the
+    /// id.
+    ///
+    /// This is useful for entities that need to know their own id, e.g.
+    /// to despawn themselves.
+    pub fn spawn_with<F>(&mut self, create: F) -> Entity
+        where F: FnOnce(Entity) -> Box<Behavior<S, Message = M>>
+    {
+        self.entities.insert_with(create)
+    }
+
+    /// Removes an entity right away and returns it.
+    pub fn despawn(&mut self, entity: Entity) -> Option<Box<Behavior<S, Message = M>>> {
+        self.entities.remove(entity)
+    }
+
+    /// Returns whether an entity is in the collection.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(entity)
+    }
+
+    /// Returns an entity.
+    pub fn get(&self, entity: Entity) -> Option<&Behavior<S, Message = M>> {
+        self.entities.get(entity).map(|behavior| &**behavior)
+    }
+
+    /// Returns an entity.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut Behavior<S, Message = M>> {
+        match self.entities.get_mut(entity) {
+            Some(behavior) => Some(&mut **behavior),
+            None => None,
+        }
+    }
+
+    /// Returns the number of entities.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns whether there are no entities.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Returns the ids of all entities, in the order they were spawned.
+    pub fn ids(&self) -> Vec<Entity> {
+        self.entities.ids()
+    }
+}
+
+impl<S, M> Default for Entities<S, M> {
+    fn default() -> Entities<S, M> {
+        Entities::new()
+    }
+}
+
+impl<S, M> Behavior<S> for Entities<S, M>
+    where M: EntityMessage<S>
+{
+    type Message = M;
+
+    fn initialize(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<M>) {
+        self.entities.each_mut(|behavior| behavior.initialize(state, frame, queue));
+    }
+
+    fn update(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<M>) {
+        self.entities.each_mut(|behavior| behavior.update(state, frame, queue));
+    }
+
+    fn handle(&mut self, state: &mut S, frame: &Frame, message: M, queue: &mut Queue<M>) {
+        match message.into_command() {
+            Ok(EntityCommand::Spawn(create)) => {
+                let entity = self.spawn_with(create);
+                if let Some(behavior) = self.entities.get_mut(entity) {
+                    behavior.initialize(state, frame, queue);
+                }
+            }
+            Ok(EntityCommand::Despawn(entity)) => {
+                self.despawn(entity);
+            }
+            Err(message) => self.handle_ref(state, frame, &message, queue),
+        }
+    }
+
+    fn handle_ref(&mut self, state: &mut S, frame: &Frame, message: &M, queue: &mut Queue<M>) {
+        match message.command() {
+            Some(&EntityCommand::Spawn(_)) => {
+                let _ = writeln!(io::stderr(), "ignoring an entity spawn passed by reference");
+            }
+            Some(&EntityCommand::Despawn(entity)) => {
+                self.despawn(entity);
+            }
+            None => {
+                self.entities
+                    .each_mut(|behavior| behavior.handle_ref(state, frame, message, queue));
+            }
+        }
+    }
+
+    fn render(&mut self, state: &S, frame: &Frame, renderer: &mut Renderer) {
+        self.entities.each_mut(|behavior| behavior.render(state, frame, renderer));
+    }
+
+    fn render_window(&mut self, state: &S, frame: &Frame, window_id: u32, renderer: &mut Renderer) {
+        self.entities
+            .each_mut(|behavior| behavior.render_window(state, frame, window_id, renderer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entities, Entity, EntityCommand, EntityMessage};
+    use frame::Frame;
+    use gameobject::Behavior;
+    use queue::{Queue, Scheduler};
+
+    /// Not `Clone`, so that it has to be passed on by reference.
+    enum Message {
+        Entities(EntityCommand<Vec<Entity>, Message>),
+        Ping,
+    }
+
+    impl EntityMessage<Vec<Entity>> for Message {
+        fn into_command(self) -> Result<EntityCommand<Vec<Entity>, Message>, Message> {
+            match self {
+                Message::Entities(command) => Ok(command),
+                message => Err(message),
+            }
+        }
+
+        fn command(&self) -> Option<&EntityCommand<Vec<Entity>, Message>> {
+            match *self {
+                Message::Entities(ref command) => Some(command),
+                Message::Ping => None,
+            }
+        }
+    }
+
+    /// Records its id in the state when it is initialized and whenever
+    /// it is pinged.
+    struct Pinged(Entity);
+
+    impl Behavior<Vec<Entity>> for Pinged {
+        type Message = Message;
+
+        fn initialize(&mut self,
+                      state: &mut Vec<Entity>,
+                      _frame: &Frame,
+                      _queue: &mut Queue<Message>) {
+            state.push(self.0);
+        }
+
+        fn handle_ref(&mut self,
+                      state: &mut Vec<Entity>,
+                      _frame: &Frame,
+                      message: &Message,
+                      _queue: &mut Queue<Message>) {
+            if let Message::Ping = *message {
+                state.push(self.0);
+            }
+        }
+    }
+
+    /// Handles `message` and then a ping, without an update in between,
+    /// and returns the recorded ids.
+    fn send(entities: &mut Entities<Vec<Entity>, Message>, message: Message) -> Vec<Entity> {
+        let mut state = Vec::new();
+        let mut messages = Vec::new();
+        let mut scheduler = Scheduler::new();
+        let frame = Frame::new();
+        let mut queue = Queue::new(&mut messages, &mut scheduler);
+        entities.handle(&mut state, &frame, message, &mut queue);
+        entities.handle(&mut state, &frame, Message::Ping, &mut queue);
+        state
+    }
+
+    #[test]
+    fn spawned_entities_are_initialized_right_away() {
+        let mut entities = Entities::new();
+        let first = entities.spawn_with(|entity| Box::new(Pinged(entity)));
+
+        let spawn = EntityCommand::spawn(|entity| Box::new(Pinged(entity)));
+        let recorded = send(&mut entities, Message::Entities(spawn));
+        let second = entities.ids()[1];
+        assert_eq!(recorded, vec![second, first, second]);
+        assert_eq!(entities.ids(), vec![first, second]);
+    }
+
+    #[test]
+    fn despawned_entities_get_no_further_messages() {
+        let mut entities = Entities::new();
+        let first = entities.spawn_with(|entity| Box::new(Pinged(entity)));
+        let second = entities.spawn_with(|entity| Box::new(Pinged(entity)));
+
+        assert_eq!(send(&mut entities, Message::Entities(EntityCommand::Despawn(first))),
+                   vec![second]);
+        assert!(!entities.contains(first));
+    }
+
+    #[test]
+    fn despawned_ids_do_not_match_new_entities() {
+        let mut entities = Entities::new();
+        let first = entities.spawn_with(|entity| Box::new(Pinged(entity)));
+        entities.despawn(first);
+
+        let second = entities.spawn_with(|entity| Box::new(Pinged(entity)));
+        assert!(second != first);
+        assert!(entities.despawn(first).is_none());
+        assert!(entities.contains(second));
+    }
+
+    #[test]
+    fn spawns_passed_by_reference_are_ignored() {
+        let mut entities = Entities::new();
+        let mut state = Vec::new();
+        let mut messages = Vec::new();
+        let mut scheduler = Scheduler::new();
+        let mut queue = Queue::new(&mut messages, &mut scheduler);
+        let spawn = EntityCommand::spawn(|entity| Box::new(Pinged(entity)));
+        entities.handle_ref(&mut state, &Frame::new(), &Message::Entities(spawn), &mut queue);
+        assert!(entities.is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+
+use sdl2::EventPump;
+use sdl2::event::Event;
+
+/// A source of SDL2 events for the game loop.
+pub trait EventSource {
+    /// Returns the next event to handle in the given tick, if any.
+    fn poll_event(&mut self, tick: u64) -> Option<Event>;
+}
+
+impl EventSource for EventPump {
+    #[inline]
+    fn poll_event(&mut self, _tick: u64) -> Option<Event> {
+        EventPump::poll_event(self)
+    }
+}
+
+/// A list of events, each delivered at a predetermined tick.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedEvents {
+    events: VecDeque<(u64, Event)>,
+}
+
+impl ScriptedEvents {
+    /// Creates an empty event script.
+    pub fn new() -> ScriptedEvents {
+        ScriptedEvents { events: VecDeque::new() }
+    }
+
+    /// Schedules `event` to be delivered at `tick`.
+    ///
+    /// Events for the same tick are delivered in the order they were
+    /// added.
+    pub fn add(&mut self, tick: u64, event: Event) {
+        let index = self.events.iter().position(|&(t, _)| t > tick).unwrap_or(self.events.len());
+        self.events.insert(index, (tick, event));
+    }
+
+    /// Returns the number of events yet to be delivered.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns whether all events have been delivered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn poll_event(&mut self, tick: u64) -> Option<Event> {
+        match self.events.front() {
+            Some(&(t, _)) if t <= tick => {}
+            _ => return None,
+        }
+        self.events.pop_front().map(|(_, event)| event)
+    }
+}
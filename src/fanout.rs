@@ -0,0 +1,167 @@
+use frame::Frame;
+use gameobject::Behavior;
+use queue::Queue;
+use renderer::Renderer;
+
+/// An owned collection of behaviors that share every message by
+/// reference.
+///
+/// Unlike the slice and tuple implementations of `Behavior`, messages
+/// do not need to implement `Clone`, as they are passed to the
+/// `handle_ref` method of each child instead of being cloned for
+/// `handle`. Children that need to own a message have to clone it
+/// themselves.
+///
+/// The children are called in the order they were added.
+pub struct Fanout<B> {
+    children: Vec<B>,
+}
+
+impl<B> Fanout<B> {
+    /// Creates an empty collection.
+    pub fn new() -> Fanout<B> {
+        Fanout { children: Vec::new() }
+    }
+
+    /// Adds a child.
+    pub fn push(&mut self, child: B) {
+        self.children.push(child);
+    }
+
+    /// Returns the children.
+    #[inline]
+    pub fn children(&self) -> &[B] {
+        &self.children
+    }
+
+    /// Returns the children, e.g. to add or remove some.
+    #[inline]
+    pub fn children_mut(&mut self) -> &mut Vec<B> {
+        &mut self.children
+    }
+
+    /// Returns the number of children.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Returns whether there are no children.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+impl<B> Default for Fanout<B> {
+    fn default() -> Fanout<B> {
+        Fanout::new()
+    }
+}
+
+impl<B> From<Vec<B>> for Fanout<B> {
+    fn from(children: Vec<B>) -> Fanout<B> {
+        Fanout { children: children }
+    }
+}
+
+impl<S, B> Behavior<S> for Fanout<B>
+    where B: Behavior<S>
+{
+    type Message = B::Message;
+
+    fn initialize(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
+        for child in &mut self.children {
+            child.initialize(state, frame, queue);
+        }
+    }
+
+    fn update(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
+        for child in &mut self.children {
+            child.update(state, frame, queue);
+        }
+    }
+
+    fn handle_ref(&mut self,
+                  state: &mut S,
+                  frame: &Frame,
+                  message: &Self::Message,
+                  queue: &mut Queue<Self::Message>) {
+        for child in &mut self.children {
+            child.handle_ref(state, frame, message, queue);
+        }
+    }
+
+    fn render(&mut self, state: &S, frame: &Frame, renderer: &mut Renderer) {
+        for child in &mut self.children {
+            child.render(state, frame, renderer);
+        }
+    }
+
+    fn render_window(&mut self, state: &S, frame: &Frame, window_id: u32, renderer: &mut Renderer) {
+        for child in &mut self.children {
+            child.render_window(state, frame, window_id, renderer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fanout;
+    use frame::Frame;
+    use gameobject::Behavior;
+    use queue::{Queue, Scheduler};
+    use scene::{Scene, SceneMessage, SceneStack, Transition};
+
+    /// Takes ownership of its messages in `handle`.
+    struct Owner(&'static str);
+
+    impl Behavior<Vec<String>> for Owner {
+        type Message = String;
+
+        fn handle(&mut self,
+                  state: &mut Vec<String>,
+                  _frame: &Frame,
+                  message: String,
+                  _queue: &mut Queue<String>) {
+            state.push(format!("{} {}", self.0, message));
+        }
+
+        fn handle_ref(&mut self,
+                      state: &mut Vec<String>,
+                      frame: &Frame,
+                      message: &String,
+                      queue: &mut Queue<String>) {
+            self.handle(state, frame, message.clone(), queue);
+        }
+    }
+
+    impl Scene<Vec<String>> for Owner {}
+
+    impl SceneMessage<Vec<String>> for String {
+        fn into_transition(self) -> Result<Transition<Vec<String>, String>, String> {
+            Err(self)
+        }
+
+        fn is_transition(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn children_that_own_messages_receive_them() {
+        let mut fanout: Fanout<Box<Behavior<Vec<String>, Message = String>>> = Fanout::new();
+        fanout.push(Box::new(Owner("child")));
+        fanout.push(Box::new(SceneStack::new(Box::new(Owner("scene")))));
+
+        let mut state = Vec::new();
+        let mut messages = Vec::new();
+        let mut scheduler = Scheduler::new();
+        let frame = Frame::new();
+        fanout.handle(&mut state,
+                      &frame,
+                      "hello".to_owned(),
+                      &mut Queue::new(&mut messages, &mut scheduler));
+        assert_eq!(state, vec!["child hello", "scene hello"]);
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use window::Viewport;
+
+/// Timing information passed to every `Behavior` callback.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    /// The amount of game time advanced by the current update.
+    ///
+    /// With a fixed tick rate, this is always the length of a tick.
+    pub delta: Duration,
+    /// The total amount of game time advanced since the game started.
+    pub elapsed: Duration,
+    /// The number of frames rendered before this one.
+    pub number: u64,
+    /// The number of updates run before this one.
+    pub tick: u64,
+    /// The fraction of a tick that has passed since the last update.
+    ///
+    /// This is only meaningful when rendering with a fixed tick rate,
+    /// and is `1.0` otherwise.
+    pub alpha: f64,
+    /// The viewport of the main window.
+    ///
+    /// This is updated between ticks when the window is resized.
+    pub viewport: Viewport,
+}
+
+impl Frame {
+    /// Creates the context for the first frame of a game.
+    pub fn new() -> Frame {
+        Frame {
+            delta: Duration::new(0, 0),
+            elapsed: Duration::new(0, 0),
+            number: 0,
+            tick: 0,
+            alpha: 1.0,
+            viewport: Viewport::new(),
+        }
+    }
+
+    /// Returns `delta` in seconds.
+    #[inline]
+    pub fn delta_secs(&self) -> f64 {
+        self.delta.as_secs_f64()
+    }
+
+    /// Returns `elapsed` in seconds.
+    #[inline]
+    pub fn elapsed_secs(&self) -> f64 {
+        self.elapsed.as_secs_f64()
+    }
+}
+
+impl Default for Frame {
+    fn default() -> Frame {
+        Frame::new()
+    }
+}
@@ -1,56 +1,212 @@
-use std::mem;
-
-use sdl2::EventPump;
+use std::io::{self, Write};
+use std::time::Instant;
 
+use capture::Capture;
 use color::Color;
+use debug::{DebugControls, Intercept};
+use event::EventSource;
 use gameobject::Behavior;
 use input::InputManager;
 use limiter::FrameLimiter;
+use plugin::Plugin;
+use profiler::{Phase, Profiler, ProfilerOverlay};
 use renderer::Renderer;
+use runner::Runner;
+use timestep::FixedTimestep;
+use window::{AutoPause, Viewport, Watch, WindowState};
 
 /// The state needed for a game.
-pub struct Game<'a, 'r: 'a> {
+pub struct Game<'a, 'r: 'a, M> {
+    runner: Runner<'a, M>,
+    fps: u32,
     limiter: FrameLimiter,
+    timestep: Option<FixedTimestep>,
+    debug: Option<DebugControls>,
+    capture: Option<Capture<'a>>,
+    auto_pause: Option<AutoPause>,
+    window: WindowState,
+    profiler: Option<Profiler>,
+    overlay: Option<ProfilerOverlay>,
     renderer: Renderer<'a, 'r>,
-    event_pump: EventPump,
+    windows: Vec<(u32, Renderer<'a, 'r>)>,
+    events: Box<EventSource + 'a>,
     clear_color: Color,
 }
 
-impl<'a, 'r> Game<'a, 'r> {
+impl<'a, 'r, M> Game<'a, 'r, M> {
     /// Creates a new game.
-    pub fn new(fps: u32, renderer: Renderer<'a, 'r>, event_pump: EventPump) -> Self {
-        Game::with_clear_color(Color(0xff, 0xff, 0xff, 0xff), fps, renderer, event_pump)
+    ///
+    /// Events are usually taken from the SDL2 `EventPump`, but any
+    /// `EventSource`, like a `Replay`, may be used instead.
+    pub fn new<E>(fps: u32, renderer: Renderer<'a, 'r>, events: E) -> Self
+        where E: EventSource + 'a
+    {
+        Game::with_clear_color(Color(0xff, 0xff, 0xff, 0xff), fps, renderer, events)
     }
 
-    pub fn with_clear_color(color: Color,
-                            fps: u32,
-                            renderer: Renderer<'a, 'r>,
-                            event_pump: EventPump)
-                            -> Self {
+    pub fn with_clear_color<E>(color: Color,
+                               fps: u32,
+                               renderer: Renderer<'a, 'r>,
+                               events: E)
+                               -> Self
+        where E: EventSource + 'a
+    {
+        let window_id = renderer.device().window_id().unwrap_or(0);
         Game {
+            runner: Runner::new(),
+            fps: fps,
             limiter: FrameLimiter::new(fps),
+            timestep: None,
+            debug: None,
+            capture: None,
+            auto_pause: None,
+            window: WindowState::new(window_id),
+            profiler: None,
+            overlay: None,
             clear_color: color,
             renderer: renderer,
-            event_pump: event_pump,
+            windows: Vec::new(),
+            events: Box::new(events),
         }
     }
 
+    /// Sets the rate at which the game is simulated.
+    ///
+    /// With `Some(tick_rate)`, `Behavior::update` is called exactly
+    /// `tick_rate` times per second of real time, which may be zero or
+    /// several times per rendered frame. Rendering is then given an
+    /// interpolation factor between the last two ticks.
+    ///
+    /// With `None` (the default), the game is updated once per frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tick rate is zero or above `MAX_TICK_RATE`, as a
+    /// tick has to last at least a nanosecond.
+    pub fn set_tick_rate(&mut self, tick_rate: Option<u32>) {
+        self.timestep = tick_rate.map(FixedTimestep::new);
+    }
+
+    /// Adds another window to the game and returns its id.
+    ///
+    /// Each frame, the window is rendered with `Behavior::render_window`
+    /// after the main window. Use a `WindowRouter` to handle the input
+    /// of each window separately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the renderer does not render to a window.
+    pub fn add_window(&mut self, renderer: Renderer<'a, 'r>) -> u32 {
+        let window_id = renderer.device().window_id().expect("renderer has no window");
+        self.windows.push((window_id, renderer));
+        window_id
+    }
+
+    /// Removes an additional window from the game and returns its
+    /// renderer.
+    pub fn remove_window(&mut self, window_id: u32) -> Option<Renderer<'a, 'r>> {
+        self.windows
+            .iter()
+            .position(|&(id, _)| id == window_id)
+            .map(|index| self.windows.remove(index).1)
+    }
+
+    /// Enables or disables the debug controls.
+    ///
+    /// See `DebugControls` for the available hotkeys.
+    pub fn set_debug_controls(&mut self, debug: Option<DebugControls>) {
+        self.debug = debug;
+    }
+
+    /// Returns the debug controls, if enabled.
+    #[inline]
+    pub fn debug_controls(&mut self) -> Option<&mut DebugControls> {
+        self.debug.as_mut()
+    }
+
+    /// Enables or disables screenshots and image sequences.
+    ///
+    /// See `Capture` for the hotkey and the saved images.
+    pub fn set_capture(&mut self, capture: Option<Capture<'a>>) {
+        self.capture = capture;
+    }
+
+    /// Returns the capture, if enabled, e.g. to start an image
+    /// sequence.
+    #[inline]
+    pub fn capture(&mut self) -> Option<&mut Capture<'a>> {
+        self.capture.as_mut()
+    }
+
+    /// Sets the policy for pausing the game while its window is not in
+    /// use, e.g. while it is minimized.
+    ///
+    /// The game is not paused automatically by default.
+    pub fn set_auto_pause(&mut self, auto_pause: Option<AutoPause>) {
+        self.auto_pause = auto_pause;
+    }
+
+    /// Returns the current viewport of the main window.
+    pub fn viewport(&self) -> Viewport {
+        Viewport::of(self.renderer.device())
+    }
+
+    /// Enables or disables profiling of the game loop.
+    ///
+    /// The profiler records how long each phase of every frame takes.
+    pub fn set_profiler(&mut self, profiler: Option<Profiler>) {
+        self.profiler = profiler;
+    }
+
+    /// Returns the profiler, if enabled.
+    #[inline]
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Sets an overlay drawing the frame times of the profiler on top
+    /// of the main window.
+    ///
+    /// The overlay is only drawn while a profiler is enabled, and is
+    /// removed if it fails to render.
+    pub fn set_profiler_overlay(&mut self, overlay: Option<ProfilerOverlay>) {
+        self.overlay = overlay;
+    }
+
+    /// Adds a plugin to the game loop.
+    ///
+    /// See `Plugin` for the hooks that are called.
+    pub fn add_plugin<P>(&mut self, plugin: P)
+        where P: Plugin<M> + 'a
+    {
+        self.runner.add_plugin(Box::new(plugin));
+    }
+
     /// Runs the game.
     ///
-    /// For each message pushed to the queue, `is_quit_message` is
-    /// called to determine if the game should quit.
-    pub fn run<B, S, I, F>(&mut self,
-                           state: &mut S,
-                           manager: &I,
-                           behavior: &mut B,
-                           is_quit_message: F)
-        where B: Behavior<S>,
-              I: InputManager<B::Message>,
-              F: Fn(&B::Message) -> bool
+    /// Each frame, events are polled once. Then, each tick, the pending
+    /// messages are handled and the behavior is updated. See
+    /// `set_tick_rate` for how ticks relate to rendered frames.
+    ///
+    /// For each message pushed to the queue, `exit` is called to
+    /// determine if the game should end. If it returns a value, the
+    /// game ends and that value is returned. This can be used to tell
+    /// the caller why the game ended, e.g. by having the behavior push
+    /// a message for quitting or for restarting with new settings.
+    ///
+    /// The game can be run again afterwards. Each run starts with empty
+    /// message queues and a fresh frame context.
+    pub fn run<B, S, I, F, R>(&mut self,
+                              state: &mut S,
+                              manager: &I,
+                              behavior: &mut B,
+                              mut exit: F)
+                              -> R
+        where B: Behavior<S, Message = M>,
+              I: InputManager<M>,
+              F: FnMut(&M) -> Option<R>
     {
-        // Create message queues
-        let mut front = Vec::new();
-        let mut back = Vec::new();
+        self.runner.reset();
 
         // Clear the screen
         self.renderer.set_draw_color(self.clear_color);
@@ -58,40 +214,161 @@ impl<'a, 'r> Game<'a, 'r> {
         self.renderer.present();
 
         // Initialize
-        behavior.initialize(state, &mut front);
+        self.runner.set_viewport(self.viewport());
+        self.runner.initialize(state, behavior);
+        // A previous run may have ended while paused
+        self.limiter.set_max_fps(self.fps);
         self.limiter.reset();
+        if let Some(ref mut timestep) = self.timestep {
+            timestep.reset();
+        }
+        let mut last_frame = Instant::now();
+        let mut was_auto_paused = false;
 
         // Main loop
-        'running: loop {
-            // Handle events
-            for event in self.event_pump.poll_iter() {
-                manager.handle(&event, &mut |m| front.push(m));
+        loop {
+            let now = Instant::now();
+            let mut elapsed = now - last_frame;
+            last_frame = now;
+
+            // While the window is not in use, only handle messages
+            let auto_paused = match self.auto_pause {
+                Some(ref policy) => self.window.is_paused(policy),
+                None => false,
+            };
+            if auto_paused != was_auto_paused {
+                let fps = match self.auto_pause {
+                    Some(policy) if auto_paused => policy.paused_fps(),
+                    _ => self.fps,
+                };
+                self.limiter.set_max_fps(fps);
+                was_auto_paused = auto_paused;
+            }
+
+            // Poll once per frame, so that input is not missed on frames
+            // without ticks, and the debug controls see their hotkeys
+            // before the tick count is decided
+            {
+                let mut watch = Watch::new(&mut self.window, &mut *self.events);
+                let mut events = Intercept::new(self.debug.as_mut(),
+                                                self.capture.as_mut(),
+                                                &mut watch);
+                self.runner.poll(manager, &mut events);
+            }
+
+            if auto_paused {
+                if let Some(value) = self.runner.idle(state, behavior, &mut exit) {
+                    return value;
+                }
+                if self.window.take_resized() {
+                    self.runner.set_viewport(self.viewport());
+                }
+                if !self.window.is_hidden() {
+                    self.render(state, behavior);
+                }
+                self.end_frame();
+                continue;
+            }
+
+            let mut paused = false;
+            let mut step = false;
+            if let Some(ref mut debug) = self.debug {
+                elapsed = debug.scale(elapsed);
+                if debug.is_paused() {
+                    paused = true;
+                    step = debug.take_step();
+                }
             }
 
-            // Let the objects handle messages
-            for m in front.drain(..) {
-                if is_quit_message(&m) {
-                    break 'running;
+            // While paused, only handle messages, so that the game can
+            // still be quit
+            if paused && !step {
+                if let Some(value) = self.runner.idle(state, behavior, &mut exit) {
+                    return value;
                 }
-                behavior.handle(state, m, &mut back);
+                if self.window.take_resized() {
+                    self.runner.set_viewport(self.viewport());
+                }
+                self.render(state, behavior);
+                self.end_frame();
+                continue;
             }
 
-            // Swap the message queues
-            mem::swap(&mut front, &mut back);
+            // A step runs a single tick of the usual length
+            let (ticks, delta) = match self.timestep {
+                Some(ref mut timestep) if paused => (1, timestep.step()),
+                Some(ref mut timestep) => (timestep.advance_by(elapsed), timestep.step()),
+                None if paused => (1, self.limiter.frame_length()),
+                None => (1, elapsed),
+            };
+
+            for _ in 0..ticks {
+                if self.window.take_resized() {
+                    self.runner.set_viewport(self.viewport());
+                }
+                if let Some(value) = self.runner.tick(state, behavior, delta, &mut exit) {
+                    return value;
+                }
+            }
+            if self.window.take_resized() {
+                self.runner.set_viewport(self.viewport());
+            }
 
-            // Update the objects and let them send messages
-            behavior.update(state, &mut front);
+            self.render(state, behavior);
+            self.end_frame();
+        }
+    }
 
-            // Clear the screen
-            self.renderer.set_draw_color(self.clear_color);
-            self.renderer.clear();
+    /// Renders a frame to every window.
+    fn render<S, B>(&mut self, state: &S, behavior: &mut B)
+        where B: Behavior<S, Message = M>
+    {
+        // Clear the screen
+        self.renderer.set_draw_color(self.clear_color);
+        self.renderer.clear();
+
+        // Render
+        let alpha = match self.timestep {
+            Some(ref timestep) => timestep.alpha(),
+            None => 1.0,
+        };
+        self.runner.render(state, behavior, &mut self.renderer, alpha);
+        if let Some(ref mut capture) = self.capture {
+            capture.capture(self.runner.frame(), &self.renderer);
+        }
+        let failed = match (self.profiler.as_ref(), self.overlay.as_ref()) {
+            (Some(profiler), Some(overlay)) => overlay.render(profiler, &mut self.renderer).err(),
+            _ => None,
+        };
+        if let Some(err) = failed {
+            // Report it once and stop drawing the overlay, rather than
+            // ending the game over a debugging aid
+            let _ = writeln!(io::stderr(), "could not render profiler overlay: {}", err);
+            self.overlay = None;
+        }
+        let start = Instant::now();
+        self.renderer.present();
+        self.runner.add_time(Phase::Present, start.elapsed());
+
+        for &mut (window_id, ref mut renderer) in &mut self.windows {
+            renderer.set_draw_color(self.clear_color);
+            renderer.clear();
+            self.runner.render_window(state, behavior, window_id, renderer);
+            let start = Instant::now();
+            renderer.present();
+            self.runner.add_time(Phase::Present, start.elapsed());
+        }
+    }
 
-            // Render
-            behavior.render(state, &mut self.renderer);
-            self.renderer.present();
+    /// Limits the frame rate and ends the frame.
+    fn end_frame(&mut self) {
+        let start = Instant::now();
+        self.limiter.limit();
+        self.runner.add_time(Phase::Sleep, start.elapsed());
 
-            // Limit frame rate
-            self.limiter.limit();
+        let times = self.runner.end_frame();
+        if let Some(ref mut profiler) = self.profiler {
+            profiler.record(times);
         }
     }
 }
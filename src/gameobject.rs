@@ -1,61 +1,302 @@
+use frame::Frame;
+use queue::Queue;
 use renderer::Renderer;
 
 /// The behavior/logic part of an objects.
+///
+/// Messages reach a behavior either by value through `handle`, or by
+/// reference through `handle_ref`. Composites with several children,
+/// like slices, tuples, `Fanout`, `Bus`, `Router`, `Entities` and
+/// derived structs with more than one field, pass every message to
+/// each child by reference, so messages never have to be cloned.
+/// Wrappers of a single child, like `Box`, `Mapped`, `SceneStack` and
+/// derived enums, pass a message on the way they got it.
+///
+/// Behaviors that may end up in a composite should therefore handle
+/// messages in `handle_ref`, and only implement `handle` as well to
+/// take ownership of messages when they are given them by value.
 pub trait Behavior<S> {
     /// The messages used by the game.
     type Message;
 
     /// Initializes the object when it is added to the game.
-    fn initialize(&mut self, _state: &mut S, _queue: &mut Vec<Self::Message>) {
+    fn initialize(&mut self, _state: &mut S, _frame: &Frame, _queue: &mut Queue<Self::Message>) {
         // Do nothing by default
     }
 
     /// Updates the object each frame.
-    fn update(&mut self, _state: &mut S, _queue: &mut Vec<Self::Message>) {
+    fn update(&mut self, _state: &mut S, _frame: &Frame, _queue: &mut Queue<Self::Message>) {
         // Do nothing by default
     }
 
     /// Handles new messages since the last frame.
+    ///
+    /// By default, the message is passed on to `handle_ref`. Implement
+    /// this as well to take ownership of messages.
     fn handle(&mut self,
-              _state: &mut S,
-              _message: Self::Message,
-              _queue: &mut Vec<Self::Message>) {
+              state: &mut S,
+              frame: &Frame,
+              message: Self::Message,
+              queue: &mut Queue<Self::Message>) {
+        self.handle_ref(state, frame, &message, queue);
+    }
+
+    /// Handles a message that is shared with other behaviors.
+    ///
+    /// This is called by composites with several children, and by
+    /// `handle` by default. Behaviors that also implement `handle` to
+    /// take ownership of messages can clone them here and pass them on.
+    fn handle_ref(&mut self,
+                  _state: &mut S,
+                  _frame: &Frame,
+                  _message: &Self::Message,
+                  _queue: &mut Queue<Self::Message>) {
         // Do nothing by default
     }
 
     /// Renders the object.
-    fn render(&mut self, _state: &S, _renderer: &mut Renderer) {
+    ///
+    /// When the game runs with a fixed tick rate, `frame.alpha` is the
+    /// fraction of a tick that has passed since the last update, and
+    /// can be used to interpolate between the previous and current
+    /// state.
+    fn render(&mut self, _state: &S, _frame: &Frame, _renderer: &mut Renderer) {
+        // Do nothing by default
+    }
+
+    /// Renders the object to one of the additional windows of a game.
+    ///
+    /// The main window is rendered with `render`, and this is called
+    /// afterwards for every window added with `Game::add_window`.
+    fn render_window(&mut self,
+                     _state: &S,
+                     _frame: &Frame,
+                     _window_id: u32,
+                     _renderer: &mut Renderer) {
         // Do nothing by default
     }
 }
 
 impl<'a, S, B> Behavior<S> for [&'a mut B]
-    where B: Behavior<S>,
-          B::Message: Clone
+    where B: Behavior<S>
 {
     type Message = B::Message;
 
-    fn initialize(&mut self, state: &mut S, queue: &mut Vec<Self::Message>) {
+    fn initialize(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
         for child in self {
-            child.initialize(state, queue);
+            child.initialize(state, frame, queue);
         }
     }
 
-    fn update(&mut self, state: &mut S, queue: &mut Vec<Self::Message>) {
+    fn update(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
         for child in self {
-            child.update(state, queue);
+            child.update(state, frame, queue);
         }
     }
 
-    fn handle(&mut self, state: &mut S, message: Self::Message, queue: &mut Vec<Self::Message>) {
+    fn handle_ref(&mut self,
+                  state: &mut S,
+                  frame: &Frame,
+                  message: &Self::Message,
+                  queue: &mut Queue<Self::Message>) {
         for child in self {
-            child.handle(state, message.clone(), queue);
+            child.handle_ref(state, frame, message, queue);
         }
     }
 
-    fn render(&mut self, state: &S, renderer: &mut Renderer) {
+    fn render(&mut self, state: &S, frame: &Frame, renderer: &mut Renderer) {
         for child in self {
-            child.render(state, renderer);
+            child.render(state, frame, renderer);
         }
     }
+
+    fn render_window(&mut self, state: &S, frame: &Frame, window_id: u32, renderer: &mut Renderer) {
+        for child in self {
+            child.render_window(state, frame, window_id, renderer);
+        }
+    }
+}
+
+impl<S, B> Behavior<S> for Box<B>
+    where B: Behavior<S> + ?Sized
+{
+    type Message = B::Message;
+
+    fn initialize(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
+        (**self).initialize(state, frame, queue);
+    }
+
+    fn update(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
+        (**self).update(state, frame, queue);
+    }
+
+    fn handle(&mut self,
+              state: &mut S,
+              frame: &Frame,
+              message: Self::Message,
+              queue: &mut Queue<Self::Message>) {
+        (**self).handle(state, frame, message, queue);
+    }
+
+    fn handle_ref(&mut self,
+                  state: &mut S,
+                  frame: &Frame,
+                  message: &Self::Message,
+                  queue: &mut Queue<Self::Message>) {
+        (**self).handle_ref(state, frame, message, queue);
+    }
+
+    fn render(&mut self, state: &S, frame: &Frame, renderer: &mut Renderer) {
+        (**self).render(state, frame, renderer);
+    }
+
+    fn render_window(&mut self, state: &S, frame: &Frame, window_id: u32, renderer: &mut Renderer) {
+        (**self).render_window(state, frame, window_id, renderer);
+    }
+}
+
+macro_rules! tuple_behavior {
+    ($($name:ident $index:tt),+) => {
+        impl<S, M, $($name),+> Behavior<S> for ($($name,)+)
+            where $($name: Behavior<S, Message = M>),+
+        {
+            type Message = M;
+
+            fn initialize(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<M>) {
+                $(self.$index.initialize(state, frame, queue);)+
+            }
+
+            fn update(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<M>) {
+                $(self.$index.update(state, frame, queue);)+
+            }
+
+            fn handle_ref(&mut self,
+                          state: &mut S,
+                          frame: &Frame,
+                          message: &M,
+                          queue: &mut Queue<M>) {
+                $(self.$index.handle_ref(state, frame, message, queue);)+
+            }
+
+            fn render(&mut self, state: &S, frame: &Frame, renderer: &mut Renderer) {
+                $(self.$index.render(state, frame, renderer);)+
+            }
+
+            fn render_window(&mut self,
+                             state: &S,
+                             frame: &Frame,
+                             window_id: u32,
+                             renderer: &mut Renderer) {
+                $(self.$index.render_window(state, frame, window_id, renderer);)+
+            }
+        }
+    }
+}
+
+// Tuples of behaviors sharing a message type act as a single behavior,
+// calling each of them in order. Messages are passed to each of them by
+// reference.
+tuple_behavior!(A 0);
+tuple_behavior!(A 0, B 1);
+tuple_behavior!(A 0, B 1, C 2);
+tuple_behavior!(A 0, B 1, C 2, D 3);
+tuple_behavior!(A 0, B 1, C 2, D 3, E 4);
+tuple_behavior!(A 0, B 1, C 2, D 3, E 4, F 5);
+tuple_behavior!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple_behavior!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+tuple_behavior!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+tuple_behavior!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+tuple_behavior!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+tuple_behavior!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+#[cfg(test)]
+mod tests {
+    use super::Behavior;
+    use frame::Frame;
+    use queue::{Queue, Scheduler};
+
+    /// Not `Clone`, so that it has to be passed on by reference.
+    struct Message(u32);
+
+    /// Records the calls it gets, tagged with its number.
+    struct Recorder(u32);
+
+    impl Behavior<Vec<(u32, &'static str)>> for Recorder {
+        type Message = Message;
+
+        fn initialize(&mut self,
+                      calls: &mut Vec<(u32, &'static str)>,
+                      _frame: &Frame,
+                      _queue: &mut Queue<Message>) {
+            calls.push((self.0, "initialize"));
+        }
+
+        fn update(&mut self,
+                  calls: &mut Vec<(u32, &'static str)>,
+                  _frame: &Frame,
+                  _queue: &mut Queue<Message>) {
+            calls.push((self.0, "update"));
+        }
+
+        fn handle_ref(&mut self,
+                      calls: &mut Vec<(u32, &'static str)>,
+                      _frame: &Frame,
+                      message: &Message,
+                      _queue: &mut Queue<Message>) {
+            assert_eq!(message.0, 7);
+            calls.push((self.0, "handle"));
+        }
+    }
+
+    /// Initializes and updates `behavior`, and passes it a message by
+    /// value and one by reference.
+    fn calls<B>(mut behavior: B) -> Vec<(u32, &'static str)>
+        where B: Behavior<Vec<(u32, &'static str)>, Message = Message>
+    {
+        let mut calls = Vec::new();
+        let mut messages = Vec::new();
+        let mut scheduler = Scheduler::new();
+        let frame = Frame::new();
+        let mut queue = Queue::new(&mut messages, &mut scheduler);
+        behavior.initialize(&mut calls, &frame, &mut queue);
+        behavior.update(&mut calls, &frame, &mut queue);
+        behavior.handle(&mut calls, &frame, Message(7), &mut queue);
+        behavior.handle_ref(&mut calls, &frame, &Message(7), &mut queue);
+        calls
+    }
+
+    /// The calls that `count` recorders in a tuple should get.
+    fn expected(count: u32) -> Vec<(u32, &'static str)> {
+        let mut expected = Vec::new();
+        for &name in &["initialize", "update", "handle", "handle"] {
+            expected.extend((0..count).map(|index| (index, name)));
+        }
+        expected
+    }
+
+    #[test]
+    fn tuples_call_their_behaviors_in_order() {
+        assert_eq!(calls((Recorder(0),)), expected(1));
+        assert_eq!(calls((Recorder(0), Recorder(1))), expected(2));
+        assert_eq!(calls((Recorder(0), Recorder(1), Recorder(2))), expected(3));
+        let twelve = (Recorder(0),
+                      Recorder(1),
+                      Recorder(2),
+                      Recorder(3),
+                      Recorder(4),
+                      Recorder(5),
+                      Recorder(6),
+                      Recorder(7),
+                      Recorder(8),
+                      Recorder(9),
+                      Recorder(10),
+                      Recorder(11));
+        assert_eq!(calls(twelve), expected(12));
+    }
+
+    #[test]
+    fn nested_tuples_are_flattened_in_order() {
+        let nested = (Recorder(0), (Recorder(1), Recorder(2)), Recorder(3));
+        assert_eq!(calls(nested), expected(4));
+    }
 }
@@ -0,0 +1,265 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use device::Device;
+use image::Image;
+use renderer::Renderer;
+
+/// The environment variable that makes `GoldenImages` replace the
+/// reference images instead of comparing against them.
+pub const BLESS_VAR: &str = "GLORIOUS_BLESS";
+
+/// A failed comparison against a reference image.
+#[derive(Debug)]
+pub enum GoldenError {
+    /// Rendering, or reading or writing an image, failed.
+    Image(String),
+    /// There is no reference image, and `GLORIOUS_BLESS` is not set.
+    ///
+    /// The rendered image has been written next to where the reference
+    /// should be.
+    MissingReference {
+        reference: PathBuf,
+        actual: PathBuf,
+    },
+    /// The image does not have the size of the reference.
+    ///
+    /// The rendered image and a diff image have been written next to
+    /// the reference, like for `Mismatch`. Pixels outside of either
+    /// image are red in the diff.
+    SizeMismatch {
+        expected_size: (u32, u32),
+        actual_size: (u32, u32),
+        actual: PathBuf,
+        diff: PathBuf,
+    },
+    /// Too many pixels differ from the reference.
+    ///
+    /// The rendered image and a diff image highlighting the differing
+    /// pixels in red have been written next to the reference.
+    Mismatch {
+        mismatched: usize,
+        actual: PathBuf,
+        diff: PathBuf,
+    },
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GoldenError::Image(ref message) => write!(f, "could not render image: {}", message),
+            GoldenError::MissingReference { ref reference, ref actual } => {
+                write!(f,
+                       "no reference image {}, see {} and set {} to accept it",
+                       reference.display(),
+                       actual.display(),
+                       BLESS_VAR)
+            }
+            GoldenError::SizeMismatch { expected_size, actual_size, ref diff, .. } => {
+                write!(f,
+                       "image is {}x{} instead of {}x{}, see {}",
+                       actual_size.0,
+                       actual_size.1,
+                       expected_size.0,
+                       expected_size.1,
+                       diff.display())
+            }
+            GoldenError::Mismatch { mismatched, ref diff, .. } => {
+                write!(f,
+                       "{} pixels differ from the reference, see {}",
+                       mismatched,
+                       diff.display())
+            }
+        }
+    }
+}
+
+impl Error for GoldenError {
+    fn description(&self) -> &str {
+        match *self {
+            GoldenError::Image(..) => "could not render image",
+            GoldenError::MissingReference { .. } => "no reference image",
+            GoldenError::SizeMismatch { .. } => "image has the wrong size",
+            GoldenError::Mismatch { .. } => "image differs from the reference",
+        }
+    }
+}
+
+impl From<String> for GoldenError {
+    fn from(message: String) -> GoldenError {
+        GoldenError::Image(message)
+    }
+}
+
+/// A directory of reference images to compare rendered output with.
+///
+/// This is meant for tests catching rendering regressions: render on a
+/// software device with `render`, and the output is compared with the
+/// reference PNG of the same name.
+///
+/// A missing reference is an error, so that a test cannot pass without
+/// anything to compare with. To write new references, or to update all
+/// references after an intended change, run the tests with the
+/// environment variable `GLORIOUS_BLESS` set, and check and commit the
+/// written images.
+///
+/// This is only available with the `golden` feature.
+#[derive(Clone, Debug)]
+pub struct GoldenImages {
+    dir: PathBuf,
+    tolerance: u8,
+    max_mismatches: usize,
+}
+
+impl GoldenImages {
+    /// Uses the reference images in `dir`.
+    ///
+    /// By default, every pixel has to match exactly.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> GoldenImages {
+        GoldenImages {
+            dir: dir.into(),
+            tolerance: 0,
+            max_mismatches: 0,
+        }
+    }
+
+    /// Sets how much each color channel of a pixel may differ from the
+    /// reference, to allow for rounding differences.
+    pub fn set_tolerance(&mut self, tolerance: u8) {
+        self.tolerance = tolerance;
+    }
+
+    /// Sets how many pixels may differ by more than the tolerance.
+    pub fn set_max_mismatches(&mut self, max_mismatches: usize) {
+        self.max_mismatches = max_mismatches;
+    }
+
+    /// Renders an image of the given size in software, and compares it
+    /// with the reference `name`.
+    ///
+    /// The renderer is cleared to black before `render` is called.
+    pub fn render<F>(&self,
+                     name: &str,
+                     width: u32,
+                     height: u32,
+                     render: F)
+                     -> Result<(), GoldenError>
+        where F: for<'a> FnOnce(&mut Renderer<'a, 'static>)
+    {
+        let device = Device::software(width, height)?;
+        let image = {
+            let mut renderer = device.create_renderer();
+            renderer.clear();
+            render(&mut renderer);
+            renderer.capture()?
+        };
+        self.check(name, &image)
+    }
+
+    /// Compares an image with the reference `name`.
+    pub fn check(&self, name: &str, image: &Image) -> Result<(), GoldenError> {
+        let path = self.path(name, "png");
+        let actual_path = self.path(name, "actual.png");
+        let diff_path = self.path(name, "diff.png");
+        if env::var_os(BLESS_VAR).is_some() {
+            fs::create_dir_all(&self.dir).map_err(|err| err.to_string())?;
+            remove_if_exists(&actual_path);
+            remove_if_exists(&diff_path);
+            return Ok(image.save_png(&path)?);
+        }
+        if !path.is_file() {
+            fs::create_dir_all(&self.dir).map_err(|err| err.to_string())?;
+            image.save_png(&actual_path)?;
+            return Err(GoldenError::MissingReference {
+                reference: path,
+                actual: actual_path,
+            });
+        }
+
+        let expected = Image::load(&path)?;
+        let expected_size = (expected.width(), expected.height());
+        let actual_size = (image.width(), image.height());
+        let (mismatched, diff) = self.diff(&expected, image);
+        if expected_size != actual_size {
+            image.save_png(&actual_path)?;
+            diff.save_png(&diff_path)?;
+            return Err(GoldenError::SizeMismatch {
+                expected_size: expected_size,
+                actual_size: actual_size,
+                actual: actual_path,
+                diff: diff_path,
+            });
+        }
+        if mismatched > self.max_mismatches {
+            image.save_png(&actual_path)?;
+            diff.save_png(&diff_path)?;
+            return Err(GoldenError::Mismatch {
+                mismatched: mismatched,
+                actual: actual_path,
+                diff: diff_path,
+            });
+        }
+        // Clean up after an earlier failure
+        remove_if_exists(&actual_path);
+        remove_if_exists(&diff_path);
+        Ok(())
+    }
+
+    /// Returns the number of differing pixels, and an image of the
+    /// actual pixels, dimmed and grayed out, with differing pixels in
+    /// red.
+    ///
+    /// If the sizes differ, the diff covers both images, and pixels
+    /// outside of either one count as differing.
+    fn diff(&self, expected: &Image, actual: &Image) -> (usize, Image) {
+        let width = expected.width().max(actual.width());
+        let height = expected.height().max(actual.height());
+        let mut mismatched = 0;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let (e, a) = match (pixel(expected, x, y), pixel(actual, x, y)) {
+                    (Some(e), Some(a)) => (e, a),
+                    _ => {
+                        mismatched += 1;
+                        pixels.extend_from_slice(&[0xff, 0x00, 0x00]);
+                        continue;
+                    }
+                };
+                let differs = e.iter()
+                    .zip(a)
+                    .any(|(&e, &a)| (e as i16 - a as i16).abs() > self.tolerance as i16);
+                if differs {
+                    mismatched += 1;
+                    pixels.extend_from_slice(&[0xff, 0x00, 0x00]);
+                } else {
+                    let gray = ((a[0] as u16 + a[1] as u16 + a[2] as u16) / 3 / 4) as u8;
+                    pixels.extend_from_slice(&[gray, gray, gray]);
+                }
+            }
+        }
+        (mismatched, Image::from_rgb(width, height, pixels))
+    }
+
+    fn path(&self, name: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, extension))
+    }
+}
+
+/// Returns the RGB values of a pixel, if it is inside the image.
+fn pixel(image: &Image, x: u32, y: u32) -> Option<&[u8]> {
+    if x >= image.width() || y >= image.height() {
+        return None;
+    }
+    let start = (y as usize * image.width() as usize + x as usize) * 3;
+    Some(&image.pixels()[start..start + 3])
+}
+
+fn remove_if_exists(path: &Path) {
+    if path.is_file() {
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use color::Color;
+use event::ScriptedEvents;
+use frame::Frame;
+use gameobject::Behavior;
+use input::InputManager;
+use plugin::Plugin;
+use renderer::Renderer;
+use runner::Runner;
+use timestep::FixedTimestep;
+use window::Viewport;
+
+/// A game loop without a live window, for deterministic tests.
+///
+/// Frames are stepped manually, events are taken from a script, and
+/// time advances by a fixed amount each frame rather than following the
+/// wall clock. Every handled message is recorded, so that a test can
+/// assert on the message stream as well as on the resulting state.
+///
+/// The renderer is typically created from `Device::software`, so that
+/// rendered frames can be inspected with `Renderer::read_pixels`.
+///
+/// # Examples
+/// ```rust
+/// # extern crate glorious;
+/// # extern crate sdl2;
+/// # fn main() {
+/// use sdl2::event::Event;
+/// use glorious::{Behavior, BoxedInputMapper, Device, Frame, Headless, Queue, ScriptedEvents};
+///
+/// struct Counter;
+///
+/// impl Behavior<u32> for Counter {
+///     type Message = ();
+///
+///     fn update(&mut self, state: &mut u32, _frame: &Frame, _queue: &mut Queue<()>) {
+///         *state += 1;
+///     }
+/// }
+///
+/// let device = Device::software(64, 64).unwrap();
+/// let mut events = ScriptedEvents::new();
+/// events.add(2, Event::Quit { timestamp: 0 });
+///
+/// let mut mapper = BoxedInputMapper::new();
+/// mapper.add(Box::new(|event: &Event, push: &mut FnMut(())| {
+///     if let Event::Quit { .. } = *event {
+///         push(());
+///     }
+/// }));
+///
+/// let mut game = Headless::new(device.create_renderer(), events);
+/// let mut count = 0;
+/// game.run(3, &mut count, &mapper, &mut Counter);
+///
+/// assert_eq!(count, 3);
+/// assert_eq!(game.messages().len(), 1);
+/// # }
+/// ```
+pub struct Headless<'a, 'r: 'a, M> {
+    runner: Runner<'a, M>,
+    renderer: Renderer<'a, 'r>,
+    events: ScriptedEvents,
+    timestep: Option<FixedTimestep>,
+    frame_time: Duration,
+    clear_color: Color,
+    messages: Vec<M>,
+    initialized: bool,
+}
+
+impl<'a, 'r, M> Headless<'a, 'r, M> {
+    /// Creates a new headless game delivering the scripted `events`.
+    ///
+    /// Each frame advances the clock by 1/60th of a second.
+    pub fn new(renderer: Renderer<'a, 'r>, events: ScriptedEvents) -> Self {
+        Headless {
+            runner: Runner::new(),
+            renderer: renderer,
+            events: events,
+            timestep: None,
+            frame_time: Duration::new(0, 1_000_000_000 / 60),
+            clear_color: Color(0xff, 0xff, 0xff, 0xff),
+            messages: Vec::new(),
+            initialized: false,
+        }
+    }
+
+    /// Sets how far the clock advances each frame.
+    pub fn set_frame_time(&mut self, frame_time: Duration) {
+        self.frame_time = frame_time;
+    }
+
+    /// Sets the rate at which the game is simulated.
+    ///
+    /// This works like `Game::set_tick_rate`, except that the ticks of
+    /// each frame are derived from the frame time.
+    pub fn set_tick_rate(&mut self, tick_rate: Option<u32>) {
+        self.timestep = tick_rate.map(FixedTimestep::new);
+    }
+
+    /// Sets the color the screen is cleared with before rendering.
+    pub fn set_clear_color(&mut self, color: Color) {
+        self.clear_color = color;
+    }
+
+    /// Adds a plugin to the game loop.
+    ///
+    /// See `Plugin` for the hooks that are called.
+    pub fn add_plugin<P>(&mut self, plugin: P)
+        where P: Plugin<M> + 'a
+    {
+        self.runner.add_plugin(Box::new(plugin));
+    }
+
+    /// Returns the event script, e.g. to add more events mid-test.
+    #[inline]
+    pub fn events_mut(&mut self) -> &mut ScriptedEvents {
+        &mut self.events
+    }
+
+    /// Returns the context of the current frame.
+    #[inline]
+    pub fn frame(&self) -> &Frame {
+        self.runner.frame()
+    }
+
+    /// Returns the renderer, e.g. to read back the rendered pixels.
+    #[inline]
+    pub fn renderer(&mut self) -> &mut Renderer<'a, 'r> {
+        &mut self.renderer
+    }
+
+    /// Returns all messages handled so far, in order.
+    #[inline]
+    pub fn messages(&self) -> &[M] {
+        &self.messages
+    }
+
+    /// Returns and forgets all messages handled so far.
+    pub fn take_messages(&mut self) -> Vec<M> {
+        self.messages.drain(..).collect()
+    }
+}
+
+impl<'a, 'r, M: Clone> Headless<'a, 'r, M> {
+    /// Runs a single frame of the game.
+    ///
+    /// The behavior is initialized on the first frame.
+    pub fn step<S, B, I>(&mut self, state: &mut S, manager: &I, behavior: &mut B)
+        where B: Behavior<S, Message = M>,
+              I: InputManager<M>
+    {
+        if !self.initialized {
+            self.runner.set_viewport(Viewport::of(self.renderer.device()));
+            self.runner.initialize(state, behavior);
+            self.initialized = true;
+        }
+
+        // Poll once per frame, like `Game`, so that frames without ticks
+        // see their input as well
+        self.runner.poll(manager, &mut self.events);
+        let (ticks, delta) = match self.timestep {
+            Some(ref mut timestep) => (timestep.advance_by(self.frame_time), timestep.step()),
+            None => (1, self.frame_time),
+        };
+
+        let messages = &mut self.messages;
+        for _ in 0..ticks {
+            self.runner.tick(state,
+                             behavior,
+                             delta,
+                             |m| {
+                                 messages.push(m.clone());
+                                 None::<()>
+                             });
+        }
+
+        self.renderer.set_draw_color(self.clear_color);
+        self.renderer.clear();
+        let alpha = match self.timestep {
+            Some(ref timestep) => timestep.alpha(),
+            None => 1.0,
+        };
+        self.runner.render(state, behavior, &mut self.renderer, alpha);
+        self.renderer.present();
+        self.runner.end_frame();
+    }
+
+    /// Runs the given number of frames.
+    pub fn run<S, B, I>(&mut self, frames: u64, state: &mut S, manager: &I, behavior: &mut B)
+        where B: Behavior<S, Message = M>,
+              I: InputManager<M>
+    {
+        for _ in 0..frames {
+            self.step(state, manager, behavior);
+        }
+    }
+}
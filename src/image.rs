@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::BlendMode;
+use sdl2::surface::Surface;
+use sdl2_image::{LoadSurface, SaveSurface};
+
+use color::Color;
+
+const BYTES_PER_PIXEL: usize = 3;
+
+/// An image in memory, e.g. a screenshot read back from a renderer.
+///
+/// Pixels are stored row by row as RGB triples, without padding.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates an image from RGB pixel data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pixels` does not hold exactly `width * height` pixels.
+    pub fn from_rgb(width: u32, height: u32, pixels: Vec<u8>) -> Image {
+        assert_eq!(pixels.len(),
+                   width as usize * height as usize * BYTES_PER_PIXEL,
+                   "wrong amount of pixel data for a {}x{} image",
+                   width,
+                   height);
+        Image {
+            width: width,
+            height: height,
+            pixels: pixels,
+        }
+    }
+
+    /// Reads an image from a file in any format supported by
+    /// `sdl2_image`, like PNG.
+    ///
+    /// Transparency is dropped.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Image, String> {
+        let mut source = Surface::from_file(path.as_ref())?;
+        let (width, height) = source.size();
+        let mut target = Surface::new(width, height, PixelFormatEnum::RGB24)?;
+        // Copy the color of transparent pixels as is
+        source.set_blend_mode(BlendMode::None)?;
+        source.blit(None, &mut target, None)?;
+
+        let row = width as usize * BYTES_PER_PIXEL;
+        let pitch = target.pitch() as usize;
+        let mut pixels = Vec::with_capacity(row * height as usize);
+        target.with_lock(|data| {
+            for y in 0..height as usize {
+                pixels.extend_from_slice(&data[y * pitch..y * pitch + row]);
+            }
+        });
+        Ok(Image::from_rgb(width, height, pixels))
+    }
+
+    /// Returns the width of the image in pixels.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the image in pixels.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the RGB pixel data of the image.
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns the color of a pixel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pixel is outside the image.
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        let i = (y as usize * self.width as usize + x as usize) * BYTES_PER_PIXEL;
+        Color(self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], 0xff)
+    }
+
+    /// Writes the image to a PNG file.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        // SDL needs mutable access to pixels it wraps
+        let mut pixels = self.pixels.clone();
+        let pitch = self.width * BYTES_PER_PIXEL as u32;
+        let surface = Surface::from_data(&mut pixels,
+                                         self.width,
+                                         self.height,
+                                         pitch,
+                                         PixelFormatEnum::RGB24)?;
+        surface.save(path.as_ref())
+    }
+}
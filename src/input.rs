@@ -1,10 +1,18 @@
 use sdl2::event::Event;
 use sdl2::keyboard::{Keycode, Mod};
 
+use window::WindowEvent;
+
 /// A manager responsible for converting SDL2 events into messages.
 ///
 /// The parameter `M` is the type of messages that the manager produces.
 pub trait InputManager<M> {
+    /// Notifies the manager that the events of a new tick are about to
+    /// be handled.
+    fn begin_tick(&self, _tick: u64) {
+        // Do nothing by default
+    }
+
     /// Pushes the messages generated from the given event to the passed
     /// handler.
     fn handle(&self, event: &Event, push: &mut FnMut(M));
@@ -150,6 +158,17 @@ impl<M: 'static> BoxedInputMapper<M> {
             }
         }));
     }
+
+    /// Adds a mapping from window events to messages.
+    pub fn add_window_events<F>(&mut self, func: F)
+        where F: 'static + Fn(WindowEvent) -> Option<M>
+    {
+        self.mappers.push(Box::new(move |event, push| {
+            if let Some(message) = WindowEvent::from_event(event).and_then(&func) {
+                push(message);
+            }
+        }));
+    }
 }
 
 impl<M: 'static + Clone> BoxedInputMapper<M> {
@@ -166,3 +185,73 @@ impl<M: 'static> InputManager<M> for BoxedInputMapper<M> {
         }
     }
 }
+
+/// Returns the id of the window an event belongs to, if any.
+pub fn event_window_id(event: &Event) -> Option<u32> {
+    use sdl2::event::Event::*;
+
+    match *event {
+        Window { window_id, .. } |
+        KeyDown { window_id, .. } |
+        KeyUp { window_id, .. } |
+        TextEditing { window_id, .. } |
+        TextInput { window_id, .. } |
+        MouseMotion { window_id, .. } |
+        MouseButtonDown { window_id, .. } |
+        MouseButtonUp { window_id, .. } |
+        MouseWheel { window_id, .. } |
+        User { window_id, .. } => Some(window_id),
+        _ => None,
+    }
+}
+
+/// An input manager that passes events on based on their window.
+///
+/// Events belonging to a window with its own manager are only passed
+/// to that manager. All other events, including those that belong to
+/// no window (like `Quit`), are passed to the fallback manager.
+pub struct WindowRouter<'a, M> {
+    windows: Vec<(u32, Box<InputManager<M> + 'a>)>,
+    fallback: Box<InputManager<M> + 'a>,
+}
+
+impl<'a, M> WindowRouter<'a, M> {
+    /// Creates a router passing all events to `fallback`.
+    pub fn new<I>(fallback: I) -> WindowRouter<'a, M>
+        where I: InputManager<M> + 'a
+    {
+        WindowRouter {
+            windows: Vec::new(),
+            fallback: Box::new(fallback),
+        }
+    }
+
+    /// Passes the events of the given window to `manager`.
+    ///
+    /// This replaces any manager previously added for the window.
+    pub fn add<I>(&mut self, window_id: u32, manager: I)
+        where I: InputManager<M> + 'a
+    {
+        self.windows.retain(|&(id, _)| id != window_id);
+        self.windows.push((window_id, Box::new(manager)));
+    }
+}
+
+impl<'a, M> InputManager<M> for WindowRouter<'a, M> {
+    fn begin_tick(&self, tick: u64) {
+        for (_, manager) in &self.windows {
+            manager.begin_tick(tick);
+        }
+        self.fallback.begin_tick(tick);
+    }
+
+    fn handle(&self, event: &Event, push: &mut FnMut(M)) {
+        let window_id = event_window_id(event);
+        for &(id, ref manager) in &self.windows {
+            if Some(id) == window_id {
+                return manager.handle(event, push);
+            }
+        }
+        self.fallback.handle(event, push);
+    }
+}
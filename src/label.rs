@@ -1,4 +1,6 @@
+use std::cell::{Cell, Ref, RefCell};
 use std::fmt::{self, Debug};
+use std::rc::Rc;
 
 use sdl2::render::Texture;
 use sdl2_ttf::Font;
@@ -7,11 +9,22 @@ use rect::Rect;
 use color::Color;
 use device::Device;
 use renderer::Renderer;
+use resources::Asset;
 
 /// A text label.
 pub struct Label {
-    texture: Texture,
-    size: (u32, u32),
+    texture: RefCell<Texture>,
+    size: Cell<(u32, u32)>,
+    source: Option<Source>,
+}
+
+/// What a label was rendered from, to render it again when its font
+/// is reloaded.
+struct Source {
+    font: Rc<Asset<Font>>,
+    version: Cell<u32>,
+    text: String,
+    color: Color,
 }
 
 impl Label {
@@ -28,23 +41,36 @@ impl Label {
     /// the font does not contain the needed glyphs.
     #[inline]
     pub fn new(font: &Font, text: &str, color: Color, device: &Device) -> Label {
-        let surface = font.render(text)
-            .blended(color)
+        let (texture, size) = render_text(font, text, color, device)
             .expect("could not render label");
-
-        let (tw, th) = surface.size();
-        let (sx, sy) = device.scale();
-        let size = ((tw as f32 / sx) as u32, (th as f32 / sy) as u32);
-
-        let texture = device.create_texture_from_surface(&surface)
-            .expect("could not upload label to texture");
-
         Label {
-            texture: texture,
-            size: size,
+            texture: RefCell::new(texture),
+            size: Cell::new(size),
+            source: None,
         }
     }
 
+    /// Creates a new label with a shared font.
+    ///
+    /// The label is rendered again with the new font when the font is
+    /// replaced, e.g. by `ResourceManager::reload_changed`. If the text
+    /// cannot be rendered with the new font, the label keeps its old
+    /// look.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` cannot be rendered by `font`, like `new`.
+    pub fn with_font(font: Rc<Asset<Font>>, text: &str, color: Color, device: &Device) -> Label {
+        let mut label = Label::new(&font.borrow(), text, color, device);
+        label.source = Some(Source {
+            version: Cell::new(font.version()),
+            font: font,
+            text: text.to_owned(),
+            color: color,
+        });
+        label
+    }
+
     /// Renders the font to the renderer.
     ///
     /// # Panics
@@ -52,33 +78,69 @@ impl Label {
     /// Panics if `Renderer::copy` would panic, given the texture of the
     /// label.
     pub fn render(&self, renderer: &mut Renderer, x: i32, y: i32) {
-        let (w, h) = self.size;
+        self.refresh(renderer.device());
+        let (w, h) = self.size.get();
         let dst = Rect::new(x, y, w, h);
-        renderer.copy(&self.texture, None, Some(dst));
+        renderer.copy(&self.texture.borrow(), None, Some(dst));
+    }
+
+    /// Renders the text again if the font of the label was replaced.
+    fn refresh(&self, device: &Device) {
+        let source = match self.source {
+            Some(ref source) if source.version.get() != source.font.version() => source,
+            _ => return,
+        };
+        // Only try once per version of the font
+        source.version.set(source.font.version());
+        let font = source.font.borrow();
+        if let Ok((texture, size)) = render_text(&font, &source.text, source.color, device) {
+            *self.texture.borrow_mut() = texture;
+            self.size.set(size);
+        }
     }
 
     /// Returns the size of the label in terms of the renderer.
     #[inline]
     pub fn size(&self) -> (u32, u32) {
-        self.size
+        self.size.get()
     }
 
     /// Returns the cached texture, if any.
     #[inline]
-    pub fn texture(&self) -> &Texture {
-        &self.texture
+    pub fn texture<'s>(&'s self) -> Ref<'s, Texture> {
+        self.texture.borrow()
     }
 
     #[inline]
     pub fn into_texture(self) -> Texture {
-        self.texture
+        self.texture.into_inner()
     }
 }
 
+/// Renders `text` to a texture, returning it along with its size in
+/// terms of the renderer.
+fn render_text(font: &Font,
+               text: &str,
+               color: Color,
+               device: &Device)
+               -> Result<(Texture, (u32, u32)), String> {
+    let surface = font.render(text)
+        .blended(color)
+        .map_err(|err| err.to_string())?;
+
+    let (tw, th) = surface.size();
+    let (sx, sy) = device.scale();
+    let size = ((tw as f32 / sx) as u32, (th as f32 / sy) as u32);
+
+    let texture = device.create_texture_from_surface(&surface)
+        .map_err(|err| err.to_string())?;
+    Ok((texture, size))
+}
+
 impl Debug for Label {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Label")
-            .field("size", &self.size)
+            .field("size", &self.size.get())
             .field("texture", &(..))
             .finish()
     }
@@ -3,34 +3,97 @@
 
 #![feature(question_mark)]
 
+extern crate num;
 extern crate ref_filter_map;
 extern crate sdl2;
 extern crate sdl2_image;
+#[cfg(feature = "audio")]
+extern crate sdl2_mixer;
 extern crate sdl2_ttf;
+#[cfg(feature = "save")]
+extern crate serde;
+#[cfg(feature = "save")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "save")]
+extern crate serde_json;
 
 #[macro_use]
 mod macros;
 
+#[cfg(feature = "audio")]
+mod audio;
+mod bus;
+mod capture;
 mod color;
+mod debug;
 mod device;
+mod entities;
+mod event;
+mod fanout;
+mod frame;
 mod game;
 mod gameobject;
+#[cfg(feature = "golden")]
+mod golden;
+mod headless;
+mod image;
 mod input;
 mod label;
 mod limiter;
+mod mapped;
+#[cfg(feature = "audio")]
+mod music;
+mod plugin;
+mod profiler;
 mod sprite;
+mod queue;
 mod rect;
 mod renderer;
+mod replay;
 mod resources;
+mod router;
+mod runner;
+#[cfg(feature = "save")]
+mod save;
+mod scene;
+mod timestep;
+mod window;
 
+#[cfg(feature = "audio")]
+pub use audio::{AudioCommand, AudioError, AudioMessage, AudioPlayer, Mixer, VolumeGroup};
+pub use bus::{Bus, KindStats, MessageKind, SubscriberId};
+pub use capture::{Capture, CaptureError};
 pub use color::Color;
+pub use debug::DebugControls;
 pub use device::Device;
+pub use entities::{Entities, Entity, EntityCommand, EntityMessage, Spawner};
+pub use event::{EventSource, ScriptedEvents};
+pub use fanout::Fanout;
+pub use frame::Frame;
 pub use game::Game;
 pub use gameobject::Behavior;
-pub use input::{InputManager, InputPattern, BoxedInputMapper};
+#[cfg(feature = "golden")]
+pub use golden::{BLESS_VAR, GoldenError, GoldenImages};
+pub use headless::Headless;
+pub use image::Image;
+pub use input::{InputManager, InputPattern, BoxedInputMapper, WindowRouter, event_window_id};
 pub use label::Label;
 pub use limiter::FrameLimiter;
+pub use mapped::Mapped;
+#[cfg(feature = "audio")]
+pub use music::{MusicCommand, Playlist, Track};
+pub use plugin::Plugin;
+pub use profiler::{FrameTimes, Phase, Profiler, ProfilerOverlay, TimeStats};
+pub use queue::{Queue, ScheduleHandle, Scheduler};
 pub use renderer::Renderer;
-pub use resources::ResourceManager;
+pub use replay::{InputRecorder, Replay, read_recording};
+pub use resources::{Asset, HotReload, ReloadError, ResourceManager};
+pub use router::{Addressed, GroupId, Recipient, Router};
+#[cfg(feature = "save")]
+pub use save::{SaveError, SaveSlots, Savable, read_save, write_save};
+pub use scene::{Scene, SceneMessage, SceneStack, Transition};
 pub use sprite::Sprite;
 pub use rect::Rect;
+pub use timestep::{FixedTimestep, MAX_TICK_RATE};
+pub use window::{AutoPause, Viewport, WindowEvent};
@@ -4,7 +4,7 @@ use std::thread;
 /// A frame limiter with a maximum frame rate.
 pub struct FrameLimiter {
     last_tick: Instant,
-    // max_fps: u32,
+    max_fps: u32,
     frame_length: Duration,
 }
 
@@ -17,11 +17,29 @@ impl FrameLimiter {
         let frame_length = Duration::new(0, 1_000_000_000 / max_fps);
         FrameLimiter {
             last_tick: Instant::now(),
-            // max_fps: max_fps,
+            max_fps: max_fps,
             frame_length: frame_length,
         }
     }
 
+    /// Returns the maximum frame rate.
+    #[inline]
+    pub fn max_fps(&self) -> u32 {
+        self.max_fps
+    }
+
+    /// Changes the maximum frame rate.
+    pub fn set_max_fps(&mut self, max_fps: u32) {
+        self.max_fps = max_fps;
+        self.frame_length = Duration::new(0, 1_000_000_000 / max_fps);
+    }
+
+    /// Returns the length of a frame at the maximum frame rate.
+    #[inline]
+    pub fn frame_length(&self) -> Duration {
+        self.frame_length
+    }
+
     /// Resets the limiter.
     pub fn reset(&mut self) {
         self.last_tick = Instant::now();
@@ -33,10 +51,6 @@ impl FrameLimiter {
         if elapsed < self.frame_length {
             let remainder = self.frame_length - elapsed;
             thread::sleep(remainder);
-        } else {
-            // let nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
-            // let fps = 1_000_000_000 as f64 / nanos as f64;
-            // println!("Too slow... FPS: {:05.2}", fps);
         }
         self.last_tick = Instant::now();
     }
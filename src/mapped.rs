@@ -0,0 +1,168 @@
+
+use frame::Frame;
+use gameobject::Behavior;
+use queue::{Queue, Scheduler};
+use renderer::Renderer;
+
+/// A behavior with its own message type, adapted to the messages of its
+/// parent.
+///
+/// This lets reusable components, like a menu widget, have their own
+/// message enum and be tested on their own. Messages the child sends
+/// are converted with `up` and passed on to the parent queue, and
+/// messages of the parent are converted with `down` and handled by the
+/// child if that returns `Some`. As `down` only borrows the message of
+/// the parent, messages are converted the same way whether they are
+/// passed to `handle` or `handle_ref`.
+///
+/// The child schedules messages on a scheduler of its own, which is
+/// polled whenever the child is initialized, updated or handles a
+/// message. Scheduled messages thus reach the parent in the tick after
+/// they are due.
+pub struct Mapped<B, C, F, G> {
+    behavior: B,
+    up: F,
+    down: G,
+    messages: Vec<C>,
+    scheduler: Scheduler<C>,
+}
+
+impl<B, C, F, G> Mapped<B, C, F, G> {
+    /// Wraps `behavior`, converting its messages with `up` and those of
+    /// the parent with `down`.
+    pub fn new(behavior: B, up: F, down: G) -> Mapped<B, C, F, G> {
+        Mapped {
+            behavior: behavior,
+            up: up,
+            down: down,
+            messages: Vec::new(),
+            scheduler: Scheduler::new(),
+        }
+    }
+
+    /// Returns the wrapped behavior.
+    #[inline]
+    pub fn inner(&self) -> &B {
+        &self.behavior
+    }
+
+    /// Returns the wrapped behavior.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut B {
+        &mut self.behavior
+    }
+
+    /// Unwraps the behavior.
+    ///
+    /// Messages the child has scheduled are dropped.
+    pub fn into_inner(self) -> B {
+        self.behavior
+    }
+
+    /// Runs `f` with a queue of the child, and sends its messages to
+    /// `queue`.
+    fn with_queue<P, H>(&mut self, frame: &Frame, queue: &mut Queue<P>, f: H)
+        where F: Fn(C) -> P,
+              H: FnOnce(&mut B, &mut Queue<C>)
+    {
+        self.scheduler.poll(frame, &mut self.messages);
+        f(&mut self.behavior,
+          &mut Queue::new(&mut self.messages, &mut self.scheduler));
+        for message in self.messages.drain(..) {
+            queue.push((self.up)(message));
+        }
+    }
+}
+
+impl<S, B, F, G, P> Behavior<S> for Mapped<B, B::Message, F, G>
+    where B: Behavior<S>,
+          F: Fn(B::Message) -> P,
+          G: Fn(&P) -> Option<B::Message>
+{
+    type Message = P;
+
+    fn initialize(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<P>) {
+        self.with_queue(frame, queue, |behavior, queue| behavior.initialize(state, frame, queue));
+    }
+
+    fn update(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<P>) {
+        self.with_queue(frame, queue, |behavior, queue| behavior.update(state, frame, queue));
+    }
+
+    fn handle_ref(&mut self, state: &mut S, frame: &Frame, message: &P, queue: &mut Queue<P>) {
+        if let Some(message) = (self.down)(message) {
+            self.with_queue(frame,
+                            queue,
+                            |behavior, queue| behavior.handle(state, frame, message, queue));
+        }
+    }
+
+    fn render(&mut self, state: &S, frame: &Frame, renderer: &mut Renderer) {
+        self.behavior.render(state, frame, renderer);
+    }
+
+    fn render_window(&mut self, state: &S, frame: &Frame, window_id: u32, renderer: &mut Renderer) {
+        self.behavior.render_window(state, frame, window_id, renderer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mapped;
+    use frame::Frame;
+    use gameobject::Behavior;
+    use queue::{Queue, Scheduler};
+
+    /// A component with messages of its own, answering every ping.
+    struct Ponger;
+
+    enum Ping {
+        Ping(u32),
+        Pong(u32),
+    }
+
+    impl Behavior<()> for Ponger {
+        type Message = Ping;
+
+        fn handle_ref(&mut self,
+                      _state: &mut (),
+                      _frame: &Frame,
+                      message: &Ping,
+                      queue: &mut Queue<Ping>) {
+            if let Ping::Ping(n) = *message {
+                queue.push(Ping::Pong(n));
+            }
+        }
+    }
+
+    /// Pings are negative numbers for the parent, and pongs positive.
+    fn up(message: Ping) -> i64 {
+        match message {
+            Ping::Ping(n) => -(n as i64),
+            Ping::Pong(n) => n as i64,
+        }
+    }
+
+    fn down(message: &i64) -> Option<Ping> {
+        if *message < 0 {
+            Some(Ping::Ping(-*message as u32))
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn messages_are_mapped_both_ways() {
+        let mut mapped = Mapped::new(Ponger, up, down);
+        let mut messages = Vec::new();
+        let mut scheduler = Scheduler::new();
+        let frame = Frame::new();
+        {
+            let mut queue = Queue::new(&mut messages, &mut scheduler);
+            mapped.handle(&mut (), &frame, -1, &mut queue);
+            mapped.handle_ref(&mut (), &frame, &-2, &mut queue);
+            mapped.handle_ref(&mut (), &frame, &3, &mut queue);
+        }
+        assert_eq!(messages, vec![1, 2]);
+    }
+}
@@ -0,0 +1,231 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+use std::time::Duration;
+
+use sdl2_mixer::Music;
+
+use audio::Mixer;
+use resources::ResourceManager;
+
+/// A music track, referred to by its path in the `ResourceManager`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    /// The path of the track.
+    pub path: Cow<'static, str>,
+    /// Where to continue from when the end of the track is reached.
+    ///
+    /// Looping tracks play until they are replaced, e.g. to skip an
+    /// intro on repeats. Tracks without a loop point are played once,
+    /// after which the playlist moves on.
+    pub loop_start: Option<Duration>,
+}
+
+impl Track {
+    /// Creates a track that is played once.
+    pub fn new<P: Into<Cow<'static, str>>>(path: P) -> Track {
+        Track {
+            path: path.into(),
+            loop_start: None,
+        }
+    }
+
+    /// Creates a track that loops from `start` once it has ended.
+    pub fn looping<P: Into<Cow<'static, str>>>(path: P, start: Duration) -> Track {
+        Track {
+            path: path.into(),
+            loop_start: Some(start),
+        }
+    }
+}
+
+/// A list of tracks to play one after another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Playlist {
+    /// The tracks, in the order they are played.
+    pub tracks: Vec<Track>,
+    /// Whether to start over after the last track.
+    pub repeat: bool,
+}
+
+impl Playlist {
+    /// Creates a playlist playing `tracks` once.
+    pub fn new(tracks: Vec<Track>) -> Playlist {
+        Playlist {
+            tracks: tracks,
+            repeat: false,
+        }
+    }
+}
+
+/// A change to the background music.
+///
+/// Changes of track take a fade duration, over which the current track
+/// fades out, and then the next one fades in. A zero duration switches
+/// tracks immediately.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MusicCommand {
+    /// Fades to a single track.
+    Play(Track, Duration),
+    /// Fades to the first track of a playlist.
+    Playlist(Playlist, Duration),
+    /// Fades to the next track of the playlist.
+    Next(Duration),
+    /// Fades the music out.
+    Stop(Duration),
+}
+
+/// The playlist and track of the background music.
+///
+/// Tracks are streamed on the music stream of the mixer, which plays a
+/// single track at a time. A change of track with a fade first fades
+/// the current track out, and then fades the next one in.
+///
+/// The jukebox has to be polled regularly, to start the next track once
+/// the current one has faded out, to move on at the end of tracks, and
+/// to continue looping tracks from their loop point. Each of these
+/// waits for the next poll. Loop points need a format that
+/// `SDL2_mixer` can seek in, like OGG or MP3.
+///
+/// It assumes it is the only user of the music stream.
+pub struct Jukebox {
+    playlist: Playlist,
+    current: Option<usize>,
+    music: Option<Rc<Music>>,
+    // While the previous track fades out, how long the current one
+    // fades in afterwards
+    fade_in: Option<Duration>,
+}
+
+impl Jukebox {
+    /// Creates a jukebox that is not playing anything.
+    pub fn new() -> Jukebox {
+        Jukebox {
+            playlist: Playlist::new(Vec::new()),
+            current: None,
+            music: None,
+            fade_in: None,
+        }
+    }
+
+    /// Runs a music command.
+    pub fn run(&mut self,
+               command: &MusicCommand,
+               mixer: &Mixer,
+               resources: &ResourceManager)
+               -> Result<(), String> {
+        match *command {
+            MusicCommand::Play(ref track, fade) => {
+                self.playlist = Playlist::new(vec![track.clone()]);
+                self.switch(Some(0), fade, mixer, resources)
+            }
+            MusicCommand::Playlist(ref playlist, fade) => {
+                self.playlist = playlist.clone();
+                let first = if playlist.tracks.is_empty() { None } else { Some(0) };
+                self.switch(first, fade, mixer, resources)
+            }
+            MusicCommand::Next(fade) => {
+                let next = self.next();
+                self.switch(next, fade, mixer, resources)
+            }
+            MusicCommand::Stop(fade) => self.switch(None, fade, mixer, resources),
+        }
+    }
+
+    /// Starts the current track once the previous one has faded out,
+    /// continues it from its loop point or moves on to the next track
+    /// once it has ended.
+    pub fn poll(&mut self, mixer: &Mixer, resources: &ResourceManager) -> Result<(), String> {
+        let index = match self.current {
+            Some(index) if !mixer.is_music_playing() => index,
+            _ => return Ok(()),
+        };
+        if let Some(fade) = self.fade_in.take() {
+            return self.play(fade, Duration::new(0, 0), mixer);
+        }
+        match self.playlist.tracks[index].loop_start {
+            Some(start) => self.play(Duration::new(0, 0), start, mixer),
+            None => {
+                let next = self.next();
+                self.switch(next, Duration::new(0, 0), mixer, resources)
+            }
+        }
+    }
+
+    /// Returns the track that is playing or being faded to.
+    pub fn current(&self) -> Option<&Track> {
+        self.current.map(|index| &self.playlist.tracks[index])
+    }
+
+    fn next(&self) -> Option<usize> {
+        let next = self.current.map_or(0, |index| index + 1);
+        if next < self.playlist.tracks.len() {
+            Some(next)
+        } else if self.playlist.repeat && !self.playlist.tracks.is_empty() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    fn switch(&mut self,
+              next: Option<usize>,
+              fade: Duration,
+              mixer: &Mixer,
+              resources: &ResourceManager)
+              -> Result<(), String> {
+        let fading_out = fade > Duration::new(0, 0) && mixer.is_music_playing();
+        if fading_out {
+            mixer.fade_out_music(fade)?;
+        } else {
+            mixer.stop_music();
+        }
+        self.current = None;
+        self.music = None;
+        self.fade_in = None;
+        let index = match next {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        self.music = Some(resources.try_music(&self.playlist.tracks[index].path)?);
+        self.current = next;
+        if fading_out {
+            self.fade_in = Some(fade);
+            Ok(())
+        } else {
+            self.play(fade, Duration::new(0, 0), mixer)
+        }
+    }
+
+    /// Plays the current track once from `position`, fading it in over
+    /// `fade`.
+    fn play(&mut self, fade: Duration, position: Duration, mixer: &Mixer) -> Result<(), String> {
+        let result = match self.music {
+            Some(ref music) => mixer.fade_in_music_from(music, Some(1), fade, position),
+            None => Ok(()),
+        };
+        if result.is_err() {
+            // Don't retry on every poll
+            self.current = None;
+            self.music = None;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Jukebox, Playlist, Track};
+
+    #[test]
+    fn next_follows_playlist() {
+        let mut jukebox = Jukebox::new();
+        jukebox.playlist = Playlist::new(vec![Track::new("a"), Track::new("b")]);
+        assert_eq!(jukebox.next(), Some(0));
+        jukebox.current = Some(0);
+        assert_eq!(jukebox.next(), Some(1));
+        jukebox.current = Some(1);
+        assert_eq!(jukebox.next(), None);
+        jukebox.playlist.repeat = true;
+        assert_eq!(jukebox.next(), Some(0));
+    }
+}
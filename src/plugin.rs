@@ -0,0 +1,65 @@
+use sdl2::event::Event;
+
+use frame::Frame;
+use renderer::Renderer;
+
+/// An extension of the game loop, for features that concern the whole
+/// game rather than a single behavior, like debug overlays or
+/// analytics.
+///
+/// Plugins are registered with `Game::add_plugin` and are called in the
+/// order they were added. Each hook is called around the corresponding
+/// phase of the loop, and plugins see every raw event and message on
+/// the way.
+pub trait Plugin<M> {
+    /// Called once per frame, before the events are polled.
+    fn before_events(&mut self, _frame: &Frame) {
+        // Do nothing by default
+    }
+
+    /// Called for each polled event, before it is turned into messages.
+    fn event(&mut self, _frame: &Frame, _event: &Event) {
+        // Do nothing by default
+    }
+
+    /// Called once per frame, after the events are polled.
+    fn after_events(&mut self, _frame: &Frame) {
+        // Do nothing by default
+    }
+
+    /// Called before the messages of a tick are handled.
+    fn before_messages(&mut self, _frame: &Frame) {
+        // Do nothing by default
+    }
+
+    /// Called for each message, before it is handled.
+    fn message(&mut self, _frame: &Frame, _message: &M) {
+        // Do nothing by default
+    }
+
+    /// Called after the messages of a tick are handled.
+    fn after_messages(&mut self, _frame: &Frame) {
+        // Do nothing by default
+    }
+
+    /// Called before the behavior is updated.
+    fn before_update(&mut self, _frame: &Frame) {
+        // Do nothing by default
+    }
+
+    /// Called after the behavior is updated.
+    fn after_update(&mut self, _frame: &Frame) {
+        // Do nothing by default
+    }
+
+    /// Called before the behavior is rendered, after the screen is
+    /// cleared.
+    fn before_render(&mut self, _frame: &Frame, _renderer: &mut Renderer) {
+        // Do nothing by default
+    }
+
+    /// Called after the behavior is rendered, to draw on top of it.
+    fn after_render(&mut self, _frame: &Frame, _renderer: &mut Renderer) {
+        // Do nothing by default
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use std::collections::vec_deque;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+use sdl2::render::BlendMode;
+
+use color::Color;
+use rect::Rect;
+use renderer::Renderer;
+
+const PHASES: [Phase; 6] = [Phase::Events,
+                            Phase::Messages,
+                            Phase::Update,
+                            Phase::Render,
+                            Phase::Present,
+                            Phase::Sleep];
+
+/// A part of a frame of the game loop.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Phase {
+    /// Polling events and turning them into messages.
+    Events,
+    /// Handling messages.
+    Messages,
+    /// Updating the behavior.
+    Update,
+    /// Rendering the behavior.
+    Render,
+    /// Presenting the rendered frame.
+    Present,
+    /// Waiting for the frame limiter.
+    Sleep,
+}
+
+impl Phase {
+    /// Returns every phase, in the order they happen in a frame.
+    pub fn all() -> &'static [Phase] {
+        &PHASES
+    }
+}
+
+/// The time spent in each phase of a frame.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FrameTimes {
+    times: [Duration; 6],
+}
+
+impl FrameTimes {
+    /// Creates empty frame times.
+    pub fn new() -> FrameTimes {
+        FrameTimes::default()
+    }
+
+    /// Returns the time spent in a phase.
+    #[inline]
+    pub fn get(&self, phase: Phase) -> Duration {
+        self.times[phase as usize]
+    }
+
+    /// Adds time spent in a phase.
+    #[inline]
+    pub fn add(&mut self, phase: Phase, time: Duration) {
+        self.times[phase as usize] += time;
+    }
+
+    /// Returns the length of the whole frame.
+    pub fn total(&self) -> Duration {
+        self.times.iter().fold(Duration::new(0, 0), |total, &time| total + time)
+    }
+}
+
+impl AddAssign for FrameTimes {
+    fn add_assign(&mut self, other: FrameTimes) {
+        for &phase in &PHASES {
+            self.add(phase, other.get(phase));
+        }
+    }
+}
+
+/// Statistics about a series of durations.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TimeStats {
+    /// The shortest duration.
+    pub min: Duration,
+    /// The mean duration.
+    pub avg: Duration,
+    /// The longest duration.
+    pub max: Duration,
+    /// The median.
+    pub p50: Duration,
+    /// The 95th percentile.
+    pub p95: Duration,
+    /// The 99th percentile.
+    pub p99: Duration,
+}
+
+impl TimeStats {
+    fn from_times(mut times: Vec<Duration>) -> TimeStats {
+        if times.is_empty() {
+            return TimeStats::default();
+        }
+        times.sort();
+        let total = times.iter().fold(0, |sum, &time| sum + time.as_nanos() as u64);
+        TimeStats {
+            min: times[0],
+            avg: Duration::from_nanos(total / times.len() as u64),
+            max: times[times.len() - 1],
+            p50: percentile(&times, 0.5),
+            p95: percentile(&times, 0.95),
+            p99: percentile(&times, 0.99),
+        }
+    }
+}
+
+/// Rolling statistics about where the time of each frame goes.
+///
+/// The profiler keeps the times of a fixed number of recent frames.
+/// Enable it with `Game::set_profiler`.
+#[derive(Clone, Debug)]
+pub struct Profiler {
+    frames: VecDeque<FrameTimes>,
+    capacity: usize,
+}
+
+impl Profiler {
+    /// Creates a profiler keeping the times of `capacity` frames.
+    pub fn new(capacity: usize) -> Profiler {
+        Profiler {
+            frames: VecDeque::with_capacity(capacity),
+            capacity: capacity,
+        }
+    }
+
+    /// Records the times of a frame, forgetting the oldest frame if
+    /// the profiler is full.
+    pub fn record(&mut self, times: FrameTimes) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(times);
+    }
+
+    /// Returns the number of recorded frames.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns whether no frames have been recorded.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns the maximum number of frames kept.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Forgets all recorded frames.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Returns the recorded frames, from oldest to newest.
+    pub fn frames<'a>(&'a self) -> vec_deque::Iter<'a, FrameTimes> {
+        self.frames.iter()
+    }
+
+    /// Returns the times of the last recorded frame.
+    pub fn last(&self) -> Option<&FrameTimes> {
+        self.frames.back()
+    }
+
+    /// Returns statistics about the time spent in a phase.
+    pub fn stats(&self, phase: Phase) -> TimeStats {
+        TimeStats::from_times(self.frames.iter().map(|f| f.get(phase)).collect())
+    }
+
+    /// Returns statistics about the length of whole frames.
+    pub fn frame_stats(&self) -> TimeStats {
+        TimeStats::from_times(self.frames.iter().map(FrameTimes::total).collect())
+    }
+}
+
+/// A graph of the recent frame times of a `Profiler`.
+///
+/// Each frame is drawn as a bar of its phases stacked on top of each
+/// other, with a line marking the frame budget.
+#[derive(Clone, Debug)]
+pub struct ProfilerOverlay {
+    rect: Rect,
+    budget: Duration,
+    background: Color,
+    colors: [Color; 6],
+}
+
+impl ProfilerOverlay {
+    /// Creates an overlay drawn in `rect`, for frames of at most
+    /// `budget`.
+    ///
+    /// The graph is scaled so that the budget is at half its height.
+    pub fn new(rect: Rect, budget: Duration) -> ProfilerOverlay {
+        ProfilerOverlay {
+            rect: rect,
+            budget: budget,
+            background: Color(0x00, 0x00, 0x00, 0xa0),
+            colors: [Color(0xe0, 0xc0, 0x40, 0xff),
+                     Color(0xe0, 0x80, 0x30, 0xff),
+                     Color(0xd0, 0x40, 0x40, 0xff),
+                     Color(0x40, 0xa0, 0xe0, 0xff),
+                     Color(0x80, 0x60, 0xd0, 0xff),
+                     Color(0x50, 0x50, 0x50, 0xff)],
+        }
+    }
+
+    /// Moves the overlay to `rect`.
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
+    }
+
+    /// Sets the color a phase is drawn with.
+    pub fn set_color(&mut self, phase: Phase, color: Color) {
+        self.colors[phase as usize] = color;
+    }
+
+    /// Draws the recent frames of `profiler`.
+    ///
+    /// The newest frame is drawn to the right, and each frame takes up
+    /// a single pixel column.
+    pub fn render(&self, profiler: &Profiler, renderer: &mut Renderer) -> Result<(), String> {
+        let old_color = renderer.draw_color();
+        let old_blend = renderer.blend_mode();
+        renderer.set_blend_mode(BlendMode::Blend);
+        let result = self.draw(profiler, renderer);
+        renderer.set_draw_color(old_color);
+        renderer.set_blend_mode(old_blend);
+        result
+    }
+
+    fn draw(&self, profiler: &Profiler, renderer: &mut Renderer) -> Result<(), String> {
+        renderer.set_draw_color(self.background);
+        renderer.fill_rect(self.rect)?;
+
+        let height = self.rect.height as u64;
+        let scale = 2 * (self.budget.as_nanos() as u64).max(1);
+        let bottom = self.rect.y + self.rect.height as i32;
+        let columns = (self.rect.width as usize).min(profiler.len());
+        let left = self.rect.x + (self.rect.width as usize - columns) as i32;
+        for (column, times) in profiler.frames().skip(profiler.len() - columns).enumerate() {
+            let x = left + column as i32;
+            let mut y = bottom;
+            let mut elapsed = 0;
+            for &phase in &PHASES {
+                elapsed += times.get(phase).as_nanos() as u64;
+                let top = bottom - (height.min(elapsed * height / scale)) as i32;
+                if top < y {
+                    renderer.set_draw_color(self.colors[phase as usize]);
+                    renderer.fill_rect(Rect::new(x, top, 1, (y - top) as u32))?;
+                    y = top;
+                }
+            }
+        }
+
+        let budget_y = bottom - (self.rect.height / 2) as i32;
+        renderer.set_draw_color(Color(0xff, 0xff, 0xff, 0xc0));
+        renderer.fill_rect(Rect::new(self.rect.x, budget_y, self.rect.width, 1))
+    }
+}
+
+fn percentile(sorted: &[Duration], fraction: f64) -> Duration {
+    let index = ((sorted.len() - 1) as f64 * fraction).round() as usize;
+    sorted[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::TimeStats;
+
+    fn millis(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn no_times_give_zeroed_stats() {
+        assert_eq!(TimeStats::from_times(Vec::new()), TimeStats::default());
+    }
+
+    #[test]
+    fn a_single_time_is_every_stat() {
+        let stats = TimeStats::from_times(vec![millis(7)]);
+        assert_eq!(stats,
+                   TimeStats {
+                       min: millis(7),
+                       avg: millis(7),
+                       max: millis(7),
+                       p50: millis(7),
+                       p95: millis(7),
+                       p99: millis(7),
+                   });
+    }
+
+    #[test]
+    fn stats_do_not_depend_on_order() {
+        let stats = TimeStats::from_times(vec![millis(3), millis(1), millis(4), millis(2)]);
+        assert_eq!(stats.min, millis(1));
+        assert_eq!(stats.max, millis(4));
+        assert_eq!(stats.avg, Duration::from_micros(2500));
+    }
+
+    #[test]
+    fn percentiles_pick_the_nearest_time() {
+        let times = (1..101).rev().map(millis).collect();
+        let stats = TimeStats::from_times(times);
+        assert_eq!(stats.avg, Duration::from_micros(50_500));
+        // Index 49.5 of the sorted times rounds up to 51 ms.
+        assert_eq!(stats.p50, millis(51));
+        assert_eq!(stats.p95, millis(95));
+        assert_eq!(stats.p99, millis(99));
+    }
+}
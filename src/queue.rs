@@ -0,0 +1,328 @@
+use std::mem;
+use std::time::Duration;
+
+use frame::Frame;
+
+/// A handle to a scheduled message, used to cancel it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ScheduleHandle(u64);
+
+#[derive(Clone, Copy, Debug)]
+enum Due {
+    Frame(u64),
+    Time(Duration),
+}
+
+impl Due {
+    fn is_due(&self, frame: &Frame) -> bool {
+        match *self {
+            Due::Frame(number) => number <= frame.number,
+            Due::Time(time) => time <= frame.elapsed,
+        }
+    }
+}
+
+/// The interval of a repeating message, and how to copy it.
+type Repeat<M> = (Duration, fn(&M) -> M);
+
+struct Entry<M> {
+    handle: ScheduleHandle,
+    due: Due,
+    message: M,
+    repeat: Option<Repeat<M>>,
+}
+
+/// Messages waiting to be sent at a later time.
+///
+/// Times are measured in game time, as given by `Frame::elapsed`, so
+/// scheduled messages are delayed along with the game when it is
+/// paused or slowed down.
+pub struct Scheduler<M> {
+    entries: Vec<Entry<M>>,
+    next_handle: u64,
+    frame: Frame,
+}
+
+impl<M> Scheduler<M> {
+    /// Creates an empty scheduler.
+    pub fn new() -> Scheduler<M> {
+        Scheduler {
+            entries: Vec::new(),
+            next_handle: 0,
+            frame: Frame::new(),
+        }
+    }
+
+    /// Returns the number of scheduled messages.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no messages are scheduled.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn add(&mut self, due: Due, message: M, repeat: Option<Repeat<M>>) -> ScheduleHandle {
+        let handle = ScheduleHandle(self.next_handle);
+        self.next_handle += 1;
+        self.entries.push(Entry {
+            handle: handle,
+            due: due,
+            message: message,
+            repeat: repeat,
+        });
+        handle
+    }
+
+    /// Schedules `message` to be sent once `delay` has passed.
+    pub fn after(&mut self, delay: Duration, message: M) -> ScheduleHandle {
+        let due = Due::Time(self.frame.elapsed + delay);
+        self.add(due, message, None)
+    }
+
+    /// Schedules `message` to be sent in the frame with the given
+    /// number, or as soon as possible if that frame has passed.
+    pub fn at_frame(&mut self, frame: u64, message: M) -> ScheduleHandle {
+        self.add(Due::Frame(frame), message, None)
+    }
+
+    /// Cancels a scheduled message.
+    ///
+    /// Returns `false` if the message was already sent or cancelled.
+    pub fn cancel(&mut self, handle: ScheduleHandle) -> bool {
+        match self.entries.iter().position(|e| e.handle == handle) {
+            Some(index) => {
+                self.entries.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the messages that are due in `frame` to `queue`.
+    ///
+    /// Messages due in a frame are sent first, by frame number, and then
+    /// messages due after a delay, by time. As the time of a frame is
+    /// only known once it has passed, the two are not interleaved, so a
+    /// message due in an earlier frame may come after one due by time.
+    /// Messages due at the same time are sent in the order they were
+    /// scheduled.
+    pub fn poll(&mut self, frame: &Frame, queue: &mut Vec<M>) {
+        self.frame = *frame;
+
+        let entries = mem::take(&mut self.entries);
+        let (mut due, pending): (Vec<_>, Vec<_>) =
+            entries.into_iter().partition(|e| e.due.is_due(frame));
+        self.entries = pending;
+        due.sort_by_key(|e| {
+            match e.due {
+                Due::Frame(number) => (0, number, Duration::new(0, 0)),
+                Due::Time(time) => (1, 0, time),
+            }
+        });
+
+        for entry in due {
+            if let Some((interval, clone)) = entry.repeat {
+                queue.push(clone(&entry.message));
+                // Repeat relative to when the message was due, so that
+                // late polls do not make the interval drift
+                let next = match entry.due {
+                    Due::Time(time) => Due::Time(time + interval),
+                    Due::Frame(_) => Due::Time(frame.elapsed + interval),
+                };
+                self.entries.push(Entry { due: next, ..entry });
+            } else {
+                queue.push(entry.message);
+            }
+        }
+    }
+}
+
+impl<M: Clone> Scheduler<M> {
+    /// Schedules `message` to be sent every time `interval` passes,
+    /// until it is cancelled.
+    pub fn every(&mut self, interval: Duration, message: M) -> ScheduleHandle {
+        let due = Due::Time(self.frame.elapsed + interval);
+        self.add(due, message, Some((interval, M::clone)))
+    }
+}
+
+impl<M> Default for Scheduler<M> {
+    fn default() -> Scheduler<M> {
+        Scheduler::new()
+    }
+}
+
+/// The queue that behaviors send their messages to.
+///
+/// Messages pushed to the queue are handled in the next tick, while
+/// messages scheduled through it are handled once they are due.
+pub struct Queue<'a, M: 'a> {
+    messages: &'a mut Vec<M>,
+    scheduler: &'a mut Scheduler<M>,
+}
+
+impl<'a, M> Queue<'a, M> {
+    /// Creates a queue pushing to `messages` and scheduling with
+    /// `scheduler`.
+    pub fn new(messages: &'a mut Vec<M>, scheduler: &'a mut Scheduler<M>) -> Queue<'a, M> {
+        Queue {
+            messages: messages,
+            scheduler: scheduler,
+        }
+    }
+
+    /// Sends a message to be handled in the next tick.
+    #[inline]
+    pub fn push(&mut self, message: M) {
+        self.messages.push(message);
+    }
+
+    /// Sends a message once `delay` has passed.
+    #[inline]
+    pub fn push_after(&mut self, delay: Duration, message: M) -> ScheduleHandle {
+        self.scheduler.after(delay, message)
+    }
+
+    /// Sends a message in the frame with the given number.
+    #[inline]
+    pub fn push_at_frame(&mut self, frame: u64, message: M) -> ScheduleHandle {
+        self.scheduler.at_frame(frame, message)
+    }
+
+    /// Cancels a scheduled message.
+    ///
+    /// Returns `false` if the message was already sent or cancelled.
+    #[inline]
+    pub fn cancel(&mut self, handle: ScheduleHandle) -> bool {
+        self.scheduler.cancel(handle)
+    }
+
+    /// Returns the messages pushed for the next tick.
+    #[inline]
+    pub fn messages(&self) -> &[M] {
+        self.messages
+    }
+
+    /// Returns the scheduler of the queue.
+    #[inline]
+    pub fn scheduler(&mut self) -> &mut Scheduler<M> {
+        self.scheduler
+    }
+}
+
+impl<'a, M: Clone> Queue<'a, M> {
+    /// Sends a message every time `interval` passes, until it is
+    /// cancelled.
+    #[inline]
+    pub fn push_every(&mut self, interval: Duration, message: M) -> ScheduleHandle {
+        self.scheduler.every(interval, message)
+    }
+}
+
+impl<'a, M> Extend<M> for Queue<'a, M> {
+    fn extend<T: IntoIterator<Item = M>>(&mut self, iter: T) {
+        self.messages.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use frame::Frame;
+
+    use super::Scheduler;
+
+    fn frame(number: u64, elapsed_ms: u64) -> Frame {
+        Frame {
+            number: number,
+            elapsed: Duration::from_millis(elapsed_ms),
+            ..Frame::new()
+        }
+    }
+
+    fn poll(scheduler: &mut Scheduler<&'static str>, frame: Frame) -> Vec<&'static str> {
+        let mut queue = Vec::new();
+        scheduler.poll(&frame, &mut queue);
+        queue
+    }
+
+    #[test]
+    fn after_sends_once_delay_has_passed() {
+        let mut scheduler = Scheduler::new();
+        scheduler.after(Duration::from_millis(100), "a");
+        assert!(poll(&mut scheduler, frame(1, 99)).is_empty());
+        assert_eq!(poll(&mut scheduler, frame(2, 100)), vec!["a"]);
+        assert!(poll(&mut scheduler, frame(3, 200)).is_empty());
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn after_is_relative_to_last_poll() {
+        let mut scheduler = Scheduler::new();
+        poll(&mut scheduler, frame(1, 1000));
+        scheduler.after(Duration::from_millis(100), "a");
+        assert!(poll(&mut scheduler, frame(2, 1050)).is_empty());
+        assert_eq!(poll(&mut scheduler, frame(3, 1100)), vec!["a"]);
+    }
+
+    #[test]
+    fn at_frame_sends_in_that_frame_or_later() {
+        let mut scheduler = Scheduler::new();
+        scheduler.at_frame(2, "a");
+        scheduler.at_frame(0, "late");
+        assert_eq!(poll(&mut scheduler, frame(1, 0)), vec!["late"]);
+        assert_eq!(poll(&mut scheduler, frame(2, 0)), vec!["a"]);
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn messages_are_sent_in_due_order() {
+        let mut scheduler = Scheduler::new();
+        scheduler.after(Duration::from_millis(30), "c");
+        scheduler.after(Duration::from_millis(10), "a");
+        scheduler.after(Duration::from_millis(30), "d");
+        scheduler.after(Duration::from_millis(20), "b");
+        assert_eq!(poll(&mut scheduler, frame(1, 50)), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn frame_messages_come_before_timed_ones() {
+        let mut scheduler = Scheduler::new();
+        scheduler.after(Duration::from_millis(10), "timed");
+        scheduler.at_frame(5, "frame");
+        assert_eq!(poll(&mut scheduler, frame(5, 50)), vec!["frame", "timed"]);
+    }
+
+    #[test]
+    fn every_repeats_without_drift() {
+        let mut scheduler = Scheduler::new();
+        scheduler.every(Duration::from_millis(100), "tick");
+        assert!(poll(&mut scheduler, frame(1, 50)).is_empty());
+        assert_eq!(poll(&mut scheduler, frame(2, 130)), vec!["tick"]);
+        assert_eq!(poll(&mut scheduler, frame(3, 200)), vec!["tick"]);
+        assert!(poll(&mut scheduler, frame(4, 299)).is_empty());
+        assert_eq!(poll(&mut scheduler, frame(5, 300)), vec!["tick"]);
+        assert_eq!(scheduler.len(), 1);
+    }
+
+    #[test]
+    fn cancel_stops_messages() {
+        let mut scheduler = Scheduler::new();
+        let once = scheduler.after(Duration::from_millis(10), "once");
+        let repeating = scheduler.every(Duration::from_millis(10), "repeating");
+        scheduler.after(Duration::from_millis(10), "kept");
+
+        assert!(scheduler.cancel(once));
+        assert!(!scheduler.cancel(once));
+        assert_eq!(poll(&mut scheduler, frame(1, 10)), vec!["repeating", "kept"]);
+
+        assert!(scheduler.cancel(repeating));
+        assert!(poll(&mut scheduler, frame(2, 100)).is_empty());
+        assert!(scheduler.is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 use sdl2::rect::Rect as Sdl2Rect;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub struct Rect {
     pub x: i32,
     pub y: i32,
@@ -1,6 +1,7 @@
 #![allow(missing_docs)]
 
 use std::cell::{Ref, RefMut};
+use std::path::Path;
 
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::rect::Point;
@@ -9,7 +10,8 @@ use sdl2::render::{BlendMode, Renderer as SdlRenderer, Texture};
 use rect::Rect;
 
 use color::Color;
-use device::Device;
+use device::{release_renderer, Device};
+use image::Image;
 
 #[inline]
 pub fn create_renderer<'a, 'r: 'a>(device: &'a Device<'r>) -> Renderer<'a, 'r> {
@@ -20,6 +22,14 @@ pub struct Renderer<'a, 'r: 'a> {
     device: &'a Device<'r>,
 }
 
+impl<'a, 'r> Drop for Renderer<'a, 'r> {
+    /// Allows a new renderer to be created for the device, e.g. to set
+    /// up a new `Game`.
+    fn drop(&mut self) {
+        release_renderer(self.device);
+    }
+}
+
 impl<'a, 'r> Renderer<'a, 'r> {
     #[inline]
     pub fn borrow(&self) -> Ref<SdlRenderer<'r>> {
@@ -157,4 +167,18 @@ impl<'a, 'r> Renderer<'a, 'r> {
                        -> Result<Vec<u8>, String> {
         self.borrow().read_pixels(rect.map(|r| r.into()), format)
     }
+
+    /// Reads back everything rendered since the last `clear`.
+    ///
+    /// This has to be called before the frame is presented.
+    pub fn capture(&self) -> Result<Image, String> {
+        let (width, height) = self.borrow().output_size()?;
+        let pixels = self.read_pixels(None, PixelFormatEnum::RGB24)?;
+        Ok(Image::from_rgb(width, height, pixels))
+    }
+
+    /// Writes what has been rendered to a PNG file, like `capture`.
+    pub fn screenshot<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        self.capture()?.save_png(path)
+    }
 }
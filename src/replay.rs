@@ -0,0 +1,1222 @@
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use num::FromPrimitive;
+use sdl2::EventPump;
+use sdl2::controller::{Axis, Button};
+use sdl2::event::{Event, WindowEventId};
+use sdl2::joystick::HatState;
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::{Mouse, MouseState};
+
+use event::{EventSource, ScriptedEvents};
+use input::InputManager;
+
+/// An input manager that records every event it handles.
+///
+/// Each event is written to a line of the output along with the tick
+/// it was handled in, and is then passed on to the wrapped manager.
+/// The recording can be played back with `Replay`.
+///
+/// Every kind of event is recorded except user events, whose data
+/// pointers cannot be written to a file. Those are skipped and counted,
+/// see `skipped`, and recording goes on.
+///
+/// Only events that reach the input manager are recorded. When used
+/// with a `Game`, the hotkeys of the debug controls and of screenshot
+/// capture are handled before the input manager sees them, so pauses,
+/// steps and screenshots are not part of the recording.
+pub struct InputRecorder<I, W: Write> {
+    inner: I,
+    writer: RefCell<W>,
+    tick: Cell<u64>,
+    skipped: Cell<u64>,
+    error: RefCell<Option<io::Error>>,
+}
+
+impl<I> InputRecorder<I, BufWriter<File>> {
+    /// Creates a recorder writing to the file at `path`.
+    ///
+    /// The file is truncated if it already exists.
+    pub fn create<P: AsRef<Path>>(inner: I, path: P) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(InputRecorder::new(inner, BufWriter::new(file)))
+    }
+}
+
+impl<I, W: Write> InputRecorder<I, W> {
+    /// Creates a recorder writing to `writer`.
+    pub fn new(inner: I, writer: W) -> InputRecorder<I, W> {
+        InputRecorder {
+            inner: inner,
+            writer: RefCell::new(writer),
+            tick: Cell::new(0),
+            skipped: Cell::new(0),
+            error: RefCell::new(None),
+        }
+    }
+
+    /// Returns the wrapped input manager.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    /// Returns the number of events that could not be recorded, like
+    /// user events.
+    #[inline]
+    pub fn skipped(&self) -> u64 {
+        self.skipped.get()
+    }
+
+    /// Returns the first error that occured while writing the
+    /// recording, if any.
+    ///
+    /// Recording stops after an error, but events are still passed on
+    /// to the wrapped manager.
+    pub fn take_error(&self) -> Option<io::Error> {
+        self.error.borrow_mut().take()
+    }
+
+    /// Flushes the recording and returns the wrapped manager and writer.
+    pub fn into_inner(self) -> io::Result<(I, W)> {
+        let mut writer = self.writer.into_inner();
+        writer.flush()?;
+        Ok((self.inner, writer))
+    }
+}
+
+impl<M, I, W> InputManager<M> for InputRecorder<I, W>
+    where I: InputManager<M>,
+          W: Write
+{
+    fn begin_tick(&self, tick: u64) {
+        self.tick.set(tick);
+        self.inner.begin_tick(tick);
+    }
+
+    fn handle(&self, event: &Event, push: &mut FnMut(M)) {
+        if !is_recordable(event) {
+            self.skipped.set(self.skipped.get() + 1);
+        } else if self.error.borrow().is_none() {
+            let mut writer = self.writer.borrow_mut();
+            if let Err(err) = write_event(&mut *writer, self.tick.get(), event) {
+                *self.error.borrow_mut() = Some(err);
+            }
+        }
+        self.inner.handle(event, push);
+    }
+}
+
+/// An event source playing back a recording made by `InputRecorder`.
+///
+/// The live event pump is still drained each tick to keep the window
+/// responsive, but apart from quit events, its events are discarded.
+pub struct Replay {
+    script: ScriptedEvents,
+    pump: EventPump,
+}
+
+impl Replay {
+    /// Creates a replay of `script`.
+    pub fn new(script: ScriptedEvents, pump: EventPump) -> Replay {
+        Replay {
+            script: script,
+            pump: pump,
+        }
+    }
+
+    /// Loads a replay from the recording at `path`.
+    pub fn open<P: AsRef<Path>>(path: P, pump: EventPump) -> io::Result<Replay> {
+        let file = File::open(path)?;
+        let script = read_recording(BufReader::new(file))?;
+        Ok(Replay::new(script, pump))
+    }
+
+    /// Returns whether all recorded events have been played back.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.script.is_empty()
+    }
+
+    /// Returns the live event pump.
+    #[inline]
+    pub fn into_event_pump(self) -> EventPump {
+        self.pump
+    }
+}
+
+impl EventSource for Replay {
+    fn poll_event(&mut self, tick: u64) -> Option<Event> {
+        while let Some(event) = self.pump.poll_event() {
+            if let Event::Quit { .. } = event {
+                return Some(event);
+            }
+        }
+        self.script.poll_event(tick)
+    }
+}
+
+/// Reads a recording made by `InputRecorder` into an event script.
+pub fn read_recording<R: BufRead>(reader: R) -> io::Result<ScriptedEvents> {
+    let mut script = ScriptedEvents::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        match parse_event(&line) {
+            Some((tick, event)) => script.add(tick, event),
+            None => {
+                let message = format!("malformed event on line {}: {:?}", number + 1, line);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+            }
+        }
+    }
+    Ok(script)
+}
+
+/// Returns whether an event can be written to a recording.
+fn is_recordable(event: &Event) -> bool {
+    !matches!(*event, Event::User { .. })
+}
+
+// The recording format has one tab-separated line per event: the tick,
+// the timestamp, the kind of event and then the fields of that kind.
+// Tabs are used since key names may contain spaces.
+
+fn write_event<W: Write>(w: &mut W, tick: u64, event: &Event) -> io::Result<()> {
+    use sdl2::event::Event::*;
+
+    match *event {
+        Quit { timestamp } => writeln!(w, "{}\t{}\tQuit", tick, timestamp),
+        Window { timestamp, window_id, win_event_id, data1, data2 } => {
+            writeln!(w,
+                     "{}\t{}\tWindow\t{}\t{:?}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     window_id,
+                     win_event_id,
+                     data1,
+                     data2)
+        }
+        KeyDown { timestamp, window_id, keycode, scancode, keymod, repeat } |
+        KeyUp { timestamp, window_id, keycode, scancode, keymod, repeat } => {
+            let kind = if let KeyDown { .. } = *event {
+                "KeyDown"
+            } else {
+                "KeyUp"
+            };
+            writeln!(w,
+                     "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     kind,
+                     window_id,
+                     keycode.map(keycode_name).unwrap_or_default(),
+                     scancode.map(scancode_name).unwrap_or_default(),
+                     keymod.bits(),
+                     repeat)
+        }
+        TextEditing { timestamp, window_id, ref text, start, length } => {
+            writeln!(w,
+                     "{}\t{}\tTextEditing\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     window_id,
+                     escape(text),
+                     start,
+                     length)
+        }
+        TextInput { timestamp, window_id, ref text } => {
+            writeln!(w,
+                     "{}\t{}\tTextInput\t{}\t{}",
+                     tick,
+                     timestamp,
+                     window_id,
+                     escape(text))
+        }
+        MouseMotion { timestamp, window_id, which, mousestate, x, y, xrel, yrel } => {
+            writeln!(w,
+                     "{}\t{}\tMouseMotion\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     window_id,
+                     which,
+                     mouse_state_flags(mousestate),
+                     x,
+                     y,
+                     xrel,
+                     yrel)
+        }
+        MouseButtonDown { timestamp, window_id, which, mouse_btn, x, y } |
+        MouseButtonUp { timestamp, window_id, which, mouse_btn, x, y } => {
+            let kind = if let MouseButtonDown { .. } = *event {
+                "MouseButtonDown"
+            } else {
+                "MouseButtonUp"
+            };
+            writeln!(w,
+                     "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     kind,
+                     window_id,
+                     which,
+                     mouse_button_index(mouse_btn),
+                     x,
+                     y)
+        }
+        MouseWheel { timestamp, window_id, which, x, y } => {
+            writeln!(w,
+                     "{}\t{}\tMouseWheel\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     window_id,
+                     which,
+                     x,
+                     y)
+        }
+        JoyAxisMotion { timestamp, which, axis_idx, value } => {
+            writeln!(w,
+                     "{}\t{}\tJoyAxisMotion\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     which,
+                     axis_idx,
+                     value)
+        }
+        JoyBallMotion { timestamp, which, ball_idx, xrel, yrel } => {
+            writeln!(w,
+                     "{}\t{}\tJoyBallMotion\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     which,
+                     ball_idx,
+                     xrel,
+                     yrel)
+        }
+        JoyHatMotion { timestamp, which, hat_idx, state } => {
+            writeln!(w,
+                     "{}\t{}\tJoyHatMotion\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     which,
+                     hat_idx,
+                     state as u8)
+        }
+        JoyButtonDown { timestamp, which, button_idx } |
+        JoyButtonUp { timestamp, which, button_idx } => {
+            writeln!(w,
+                     "{}\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     event_kind(event),
+                     which,
+                     button_idx)
+        }
+        ControllerAxisMotion { timestamp, which, axis, value } => {
+            writeln!(w,
+                     "{}\t{}\tControllerAxisMotion\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     which,
+                     axis.string(),
+                     value)
+        }
+        ControllerButtonDown { timestamp, which, button } |
+        ControllerButtonUp { timestamp, which, button } => {
+            writeln!(w,
+                     "{}\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     event_kind(event),
+                     which,
+                     button.string())
+        }
+        JoyDeviceAdded { timestamp, which } |
+        JoyDeviceRemoved { timestamp, which } |
+        ControllerDeviceAdded { timestamp, which } |
+        ControllerDeviceRemoved { timestamp, which } |
+        ControllerDeviceRemapped { timestamp, which } => {
+            writeln!(w, "{}\t{}\t{}\t{}", tick, timestamp, event_kind(event), which)
+        }
+        FingerDown { timestamp, touch_id, finger_id, x, y, dx, dy, pressure } |
+        FingerUp { timestamp, touch_id, finger_id, x, y, dx, dy, pressure } |
+        FingerMotion { timestamp, touch_id, finger_id, x, y, dx, dy, pressure } => {
+            writeln!(w,
+                     "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     event_kind(event),
+                     touch_id,
+                     finger_id,
+                     x,
+                     y,
+                     dx,
+                     dy,
+                     pressure)
+        }
+        DollarGesture { timestamp, touch_id, gesture_id, num_fingers, error, x, y } |
+        DollarRecord { timestamp, touch_id, gesture_id, num_fingers, error, x, y } => {
+            writeln!(w,
+                     "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     event_kind(event),
+                     touch_id,
+                     gesture_id,
+                     num_fingers,
+                     error,
+                     x,
+                     y)
+        }
+        MultiGesture { timestamp, touch_id, d_theta, d_dist, x, y, num_fingers } => {
+            writeln!(w,
+                     "{}\t{}\tMultiGesture\t{}\t{}\t{}\t{}\t{}\t{}",
+                     tick,
+                     timestamp,
+                     touch_id,
+                     d_theta,
+                     d_dist,
+                     x,
+                     y,
+                     num_fingers)
+        }
+        DropFile { timestamp, ref filename } => {
+            writeln!(w, "{}\t{}\tDropFile\t{}", tick, timestamp, escape(filename))
+        }
+        Unknown { timestamp, type_ } => {
+            writeln!(w, "{}\t{}\tUnknown\t{}", tick, timestamp, type_)
+        }
+        User { .. } => {
+            Err(io::Error::new(io::ErrorKind::InvalidInput,
+                               "user events hold pointers and cannot be recorded"))
+        }
+        AppTerminating { timestamp } |
+        AppLowMemory { timestamp } |
+        AppWillEnterBackground { timestamp } |
+        AppDidEnterBackground { timestamp } |
+        AppWillEnterForeground { timestamp } |
+        AppDidEnterForeground { timestamp } |
+        ClipboardUpdate { timestamp } => {
+            writeln!(w, "{}\t{}\t{}", tick, timestamp, event_kind(event))
+        }
+    }
+}
+
+/// Returns the name of an event in the recording format, for events
+/// that share their fields with others.
+fn event_kind(event: &Event) -> &'static str {
+    use sdl2::event::Event::*;
+
+    match *event {
+        JoyButtonDown { .. } => "JoyButtonDown",
+        JoyButtonUp { .. } => "JoyButtonUp",
+        JoyDeviceAdded { .. } => "JoyDeviceAdded",
+        JoyDeviceRemoved { .. } => "JoyDeviceRemoved",
+        ControllerButtonDown { .. } => "ControllerButtonDown",
+        ControllerButtonUp { .. } => "ControllerButtonUp",
+        ControllerDeviceAdded { .. } => "ControllerDeviceAdded",
+        ControllerDeviceRemoved { .. } => "ControllerDeviceRemoved",
+        ControllerDeviceRemapped { .. } => "ControllerDeviceRemapped",
+        FingerDown { .. } => "FingerDown",
+        FingerUp { .. } => "FingerUp",
+        FingerMotion { .. } => "FingerMotion",
+        DollarGesture { .. } => "DollarGesture",
+        DollarRecord { .. } => "DollarRecord",
+        AppTerminating { .. } => "AppTerminating",
+        AppLowMemory { .. } => "AppLowMemory",
+        AppWillEnterBackground { .. } => "AppWillEnterBackground",
+        AppDidEnterBackground { .. } => "AppDidEnterBackground",
+        AppWillEnterForeground { .. } => "AppWillEnterForeground",
+        AppDidEnterForeground { .. } => "AppDidEnterForeground",
+        ClipboardUpdate { .. } => "ClipboardUpdate",
+        _ => unreachable!("event kind is written directly"),
+    }
+}
+
+fn parse_event(line: &str) -> Option<(u64, Event)> {
+    let mut fields = line.split('\t');
+    let tick = field(&mut fields)?;
+    let timestamp = field(&mut fields)?;
+    let event = match fields.next()? {
+        "Quit" => Event::Quit { timestamp: timestamp },
+        "Window" => {
+            Event::Window {
+                timestamp: timestamp,
+                window_id: field(&mut fields)?,
+                win_event_id: window_event_id(fields.next()?)?,
+                data1: field(&mut fields)?,
+                data2: field(&mut fields)?,
+            }
+        }
+        kind @ "KeyDown" | kind @ "KeyUp" => {
+            let window_id = field(&mut fields)?;
+            let keycode = match fields.next()? {
+                "" => None,
+                name => Some(parse_keycode(name)?),
+            };
+            let scancode = match fields.next()? {
+                "" => None,
+                name => Some(parse_scancode(name)?),
+            };
+            let keymod = Mod::from_bits_truncate(field(&mut fields)?);
+            let repeat = field(&mut fields)?;
+            if kind == "KeyDown" {
+                Event::KeyDown {
+                    timestamp: timestamp,
+                    window_id: window_id,
+                    keycode: keycode,
+                    scancode: scancode,
+                    keymod: keymod,
+                    repeat: repeat,
+                }
+            } else {
+                Event::KeyUp {
+                    timestamp: timestamp,
+                    window_id: window_id,
+                    keycode: keycode,
+                    scancode: scancode,
+                    keymod: keymod,
+                    repeat: repeat,
+                }
+            }
+        }
+        "TextEditing" => {
+            Event::TextEditing {
+                timestamp: timestamp,
+                window_id: field(&mut fields)?,
+                text: unescape(fields.next()?),
+                start: field(&mut fields)?,
+                length: field(&mut fields)?,
+            }
+        }
+        "TextInput" => {
+            Event::TextInput {
+                timestamp: timestamp,
+                window_id: field(&mut fields)?,
+                text: unescape(fields.next()?),
+            }
+        }
+        "MouseMotion" => {
+            Event::MouseMotion {
+                timestamp: timestamp,
+                window_id: field(&mut fields)?,
+                which: field(&mut fields)?,
+                mousestate: MouseState::from_flags(field(&mut fields)?),
+                x: field(&mut fields)?,
+                y: field(&mut fields)?,
+                xrel: field(&mut fields)?,
+                yrel: field(&mut fields)?,
+            }
+        }
+        kind @ "MouseButtonDown" | kind @ "MouseButtonUp" => {
+            let window_id = field(&mut fields)?;
+            let which = field(&mut fields)?;
+            let mouse_btn = Mouse::from_ll(field(&mut fields)?);
+            let x = field(&mut fields)?;
+            let y = field(&mut fields)?;
+            if kind == "MouseButtonDown" {
+                Event::MouseButtonDown {
+                    timestamp: timestamp,
+                    window_id: window_id,
+                    which: which,
+                    mouse_btn: mouse_btn,
+                    x: x,
+                    y: y,
+                }
+            } else {
+                Event::MouseButtonUp {
+                    timestamp: timestamp,
+                    window_id: window_id,
+                    which: which,
+                    mouse_btn: mouse_btn,
+                    x: x,
+                    y: y,
+                }
+            }
+        }
+        "MouseWheel" => {
+            Event::MouseWheel {
+                timestamp: timestamp,
+                window_id: field(&mut fields)?,
+                which: field(&mut fields)?,
+                x: field(&mut fields)?,
+                y: field(&mut fields)?,
+            }
+        }
+        "JoyAxisMotion" => {
+            Event::JoyAxisMotion {
+                timestamp: timestamp,
+                which: field(&mut fields)?,
+                axis_idx: field(&mut fields)?,
+                value: field(&mut fields)?,
+            }
+        }
+        "JoyBallMotion" => {
+            Event::JoyBallMotion {
+                timestamp: timestamp,
+                which: field(&mut fields)?,
+                ball_idx: field(&mut fields)?,
+                xrel: field(&mut fields)?,
+                yrel: field(&mut fields)?,
+            }
+        }
+        "JoyHatMotion" => {
+            Event::JoyHatMotion {
+                timestamp: timestamp,
+                which: field(&mut fields)?,
+                hat_idx: field(&mut fields)?,
+                state: hat_state(field(&mut fields)?)?,
+            }
+        }
+        kind @ "JoyButtonDown" | kind @ "JoyButtonUp" => {
+            let which = field(&mut fields)?;
+            let button_idx = field(&mut fields)?;
+            if kind == "JoyButtonDown" {
+                Event::JoyButtonDown {
+                    timestamp: timestamp,
+                    which: which,
+                    button_idx: button_idx,
+                }
+            } else {
+                Event::JoyButtonUp {
+                    timestamp: timestamp,
+                    which: which,
+                    button_idx: button_idx,
+                }
+            }
+        }
+        "ControllerAxisMotion" => {
+            Event::ControllerAxisMotion {
+                timestamp: timestamp,
+                which: field(&mut fields)?,
+                axis: Axis::from_string(fields.next()?)?,
+                value: field(&mut fields)?,
+            }
+        }
+        kind @ "ControllerButtonDown" | kind @ "ControllerButtonUp" => {
+            let which = field(&mut fields)?;
+            let button = Button::from_string(fields.next()?)?;
+            if kind == "ControllerButtonDown" {
+                Event::ControllerButtonDown {
+                    timestamp: timestamp,
+                    which: which,
+                    button: button,
+                }
+            } else {
+                Event::ControllerButtonUp {
+                    timestamp: timestamp,
+                    which: which,
+                    button: button,
+                }
+            }
+        }
+        "JoyDeviceAdded" => {
+            Event::JoyDeviceAdded {
+                timestamp: timestamp,
+                which: field(&mut fields)?,
+            }
+        }
+        "JoyDeviceRemoved" => {
+            Event::JoyDeviceRemoved {
+                timestamp: timestamp,
+                which: field(&mut fields)?,
+            }
+        }
+        "ControllerDeviceAdded" => {
+            Event::ControllerDeviceAdded {
+                timestamp: timestamp,
+                which: field(&mut fields)?,
+            }
+        }
+        "ControllerDeviceRemoved" => {
+            Event::ControllerDeviceRemoved {
+                timestamp: timestamp,
+                which: field(&mut fields)?,
+            }
+        }
+        "ControllerDeviceRemapped" => {
+            Event::ControllerDeviceRemapped {
+                timestamp: timestamp,
+                which: field(&mut fields)?,
+            }
+        }
+        kind @ "FingerDown" | kind @ "FingerUp" | kind @ "FingerMotion" => {
+            let touch_id = field(&mut fields)?;
+            let finger_id = field(&mut fields)?;
+            let x = field(&mut fields)?;
+            let y = field(&mut fields)?;
+            let dx = field(&mut fields)?;
+            let dy = field(&mut fields)?;
+            let pressure = field(&mut fields)?;
+            match kind {
+                "FingerDown" => {
+                    Event::FingerDown {
+                        timestamp: timestamp,
+                        touch_id: touch_id,
+                        finger_id: finger_id,
+                        x: x,
+                        y: y,
+                        dx: dx,
+                        dy: dy,
+                        pressure: pressure,
+                    }
+                }
+                "FingerUp" => {
+                    Event::FingerUp {
+                        timestamp: timestamp,
+                        touch_id: touch_id,
+                        finger_id: finger_id,
+                        x: x,
+                        y: y,
+                        dx: dx,
+                        dy: dy,
+                        pressure: pressure,
+                    }
+                }
+                _ => {
+                    Event::FingerMotion {
+                        timestamp: timestamp,
+                        touch_id: touch_id,
+                        finger_id: finger_id,
+                        x: x,
+                        y: y,
+                        dx: dx,
+                        dy: dy,
+                        pressure: pressure,
+                    }
+                }
+            }
+        }
+        kind @ "DollarGesture" | kind @ "DollarRecord" => {
+            let touch_id = field(&mut fields)?;
+            let gesture_id = field(&mut fields)?;
+            let num_fingers = field(&mut fields)?;
+            let error = field(&mut fields)?;
+            let x = field(&mut fields)?;
+            let y = field(&mut fields)?;
+            if kind == "DollarGesture" {
+                Event::DollarGesture {
+                    timestamp: timestamp,
+                    touch_id: touch_id,
+                    gesture_id: gesture_id,
+                    num_fingers: num_fingers,
+                    error: error,
+                    x: x,
+                    y: y,
+                }
+            } else {
+                Event::DollarRecord {
+                    timestamp: timestamp,
+                    touch_id: touch_id,
+                    gesture_id: gesture_id,
+                    num_fingers: num_fingers,
+                    error: error,
+                    x: x,
+                    y: y,
+                }
+            }
+        }
+        "MultiGesture" => {
+            Event::MultiGesture {
+                timestamp: timestamp,
+                touch_id: field(&mut fields)?,
+                d_theta: field(&mut fields)?,
+                d_dist: field(&mut fields)?,
+                x: field(&mut fields)?,
+                y: field(&mut fields)?,
+                num_fingers: field(&mut fields)?,
+            }
+        }
+        "DropFile" => {
+            Event::DropFile {
+                timestamp: timestamp,
+                filename: unescape(fields.next()?),
+            }
+        }
+        "Unknown" => {
+            Event::Unknown {
+                timestamp: timestamp,
+                type_: field(&mut fields)?,
+            }
+        }
+        "AppTerminating" => Event::AppTerminating { timestamp: timestamp },
+        "AppLowMemory" => Event::AppLowMemory { timestamp: timestamp },
+        "AppWillEnterBackground" => Event::AppWillEnterBackground { timestamp: timestamp },
+        "AppDidEnterBackground" => Event::AppDidEnterBackground { timestamp: timestamp },
+        "AppWillEnterForeground" => Event::AppWillEnterForeground { timestamp: timestamp },
+        "AppDidEnterForeground" => Event::AppDidEnterForeground { timestamp: timestamp },
+        "ClipboardUpdate" => Event::ClipboardUpdate { timestamp: timestamp },
+        _ => return None,
+    };
+    // Trailing fields mean the line was not written by `write_event`
+    if fields.next().is_some() {
+        return None;
+    }
+    Some((tick, event))
+}
+
+#[inline]
+fn field<'a, I, T>(fields: &mut I) -> Option<T>
+    where I: Iterator<Item = &'a str>,
+          T: FromStr
+{
+    fields.next().and_then(|f| f.parse().ok())
+}
+
+fn window_event_id(name: &str) -> Option<WindowEventId> {
+    use sdl2::event::WindowEventId::*;
+
+    Some(match name {
+        "None" => None,
+        "Shown" => Shown,
+        "Hidden" => Hidden,
+        "Exposed" => Exposed,
+        "Moved" => Moved,
+        "Resized" => Resized,
+        "SizeChanged" => SizeChanged,
+        "Minimized" => Minimized,
+        "Maximized" => Maximized,
+        "Restored" => Restored,
+        "Enter" => Enter,
+        "Leave" => Leave,
+        "FocusGained" => FocusGained,
+        "FocusLost" => FocusLost,
+        "Close" => Close,
+        _ => return Option::None,
+    })
+}
+
+// Keys are written by name where the name maps back to the same key,
+// and by number after a `#` otherwise.
+
+fn keycode_name(keycode: Keycode) -> String {
+    let name = keycode.name();
+    if !name.is_empty() && Keycode::from_name(&name) == Some(keycode) {
+        name
+    } else {
+        format!("#{}", keycode as i32)
+    }
+}
+
+fn parse_keycode(name: &str) -> Option<Keycode> {
+    match name.strip_prefix('#') {
+        Some(number) => Keycode::from_i64(number.parse().ok()?),
+        None => Keycode::from_name(name),
+    }
+}
+
+fn scancode_name(scancode: Scancode) -> String {
+    let name = scancode.name();
+    if !name.is_empty() && Scancode::from_name(name) == Some(scancode) {
+        name.to_owned()
+    } else {
+        format!("#{}", scancode as i32)
+    }
+}
+
+fn parse_scancode(name: &str) -> Option<Scancode> {
+    match name.strip_prefix('#') {
+        Some(number) => Scancode::from_i64(number.parse().ok()?),
+        None => Scancode::from_name(name),
+    }
+}
+
+fn hat_state(raw: u8) -> Option<HatState> {
+    Some(match raw {
+        0 => HatState::Centered,
+        1 => HatState::Up,
+        2 => HatState::Right,
+        4 => HatState::Down,
+        8 => HatState::Left,
+        3 => HatState::RightUp,
+        6 => HatState::RightDown,
+        9 => HatState::LeftUp,
+        12 => HatState::Leftdown,
+        _ => return None,
+    })
+}
+
+fn mouse_button_index(button: Mouse) -> u8 {
+    match button {
+        Mouse::Left => 1,
+        Mouse::Middle => 2,
+        Mouse::Right => 3,
+        Mouse::X1 => 4,
+        Mouse::X2 => 5,
+        Mouse::Unknown(index) => index,
+    }
+}
+
+fn mouse_state_flags(state: MouseState) -> u32 {
+    [state.left(), state.middle(), state.right(), state.x1(), state.x2()]
+        .iter()
+        .enumerate()
+        .fold(0, |flags, (i, &down)| if down { flags | 1 << i } else { flags })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use sdl2::controller::{Axis, Button};
+    use sdl2::event::{Event, WindowEventId};
+    use sdl2::joystick::HatState;
+    use sdl2::keyboard::{Keycode, Mod, Scancode};
+    use sdl2::mouse::{Mouse, MouseState};
+
+    use input::{BoxedInputMapper, InputManager};
+
+    use super::{escape, parse_event, read_recording, unescape, write_event, InputRecorder};
+
+    fn round_trip(event: Event) {
+        let mut line = Vec::new();
+        write_event(&mut line, 7, &event).unwrap();
+        let line = String::from_utf8(line).unwrap();
+        assert!(line.ends_with('\n'));
+        match parse_event(line.trim_end_matches('\n')) {
+            Some((tick, parsed)) => {
+                assert_eq!(tick, 7);
+                assert!(parsed == event, "{:?} changed in line {:?}", event, line);
+            }
+            None => panic!("could not parse {:?}", line),
+        }
+    }
+
+    #[test]
+    fn keyboard_events_round_trip() {
+        round_trip(Event::KeyDown {
+            timestamp: 1,
+            window_id: 2,
+            keycode: Some(Keycode::KpEnter),
+            scancode: Some(Scancode::KpEnter),
+            keymod: Mod::from_bits_truncate(0x0041),
+            repeat: true,
+        });
+        round_trip(Event::KeyUp {
+            timestamp: 1,
+            window_id: 2,
+            keycode: None,
+            scancode: None,
+            keymod: Mod::empty(),
+            repeat: false,
+        });
+        round_trip(Event::TextInput {
+            timestamp: 3,
+            window_id: 1,
+            text: "tab\there\\".to_owned(),
+        });
+        round_trip(Event::TextEditing {
+            timestamp: 3,
+            window_id: 1,
+            text: "new\nline".to_owned(),
+            start: 2,
+            length: -1,
+        });
+    }
+
+    #[test]
+    fn every_keycode_round_trips() {
+        // Keycodes are either characters or scancodes with bit 30 set
+        for code in (0..0x80).chain(0x4000_0000..0x4000_0200) {
+            let keycode = match ::num::FromPrimitive::from_i64(code) {
+                Some(keycode) => keycode,
+                None => continue,
+            };
+            let scancode = Scancode::from_keycode(keycode);
+            round_trip(Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: Some(keycode),
+                scancode: scancode,
+                keymod: Mod::empty(),
+                repeat: false,
+            });
+        }
+    }
+
+    #[test]
+    fn mouse_and_window_events_round_trip() {
+        round_trip(Event::Quit { timestamp: 9 });
+        round_trip(Event::Window {
+            timestamp: 1,
+            window_id: 2,
+            win_event_id: WindowEventId::Resized,
+            data1: 640,
+            data2: -480,
+        });
+        round_trip(Event::MouseMotion {
+            timestamp: 1,
+            window_id: 2,
+            which: 0,
+            mousestate: MouseState::from_flags(0b10101),
+            x: 10,
+            y: -20,
+            xrel: 3,
+            yrel: -4,
+        });
+        round_trip(Event::MouseButtonDown {
+            timestamp: 1,
+            window_id: 2,
+            which: 0,
+            mouse_btn: Mouse::Unknown(9),
+            x: 5,
+            y: 6,
+        });
+        round_trip(Event::MouseButtonUp {
+            timestamp: 1,
+            window_id: 2,
+            which: 0,
+            mouse_btn: Mouse::X2,
+            x: 5,
+            y: 6,
+        });
+        round_trip(Event::MouseWheel {
+            timestamp: 1,
+            window_id: 2,
+            which: 0,
+            x: 0,
+            y: -3,
+        });
+    }
+
+    #[test]
+    fn joystick_and_controller_events_round_trip() {
+        round_trip(Event::JoyAxisMotion {
+            timestamp: 1,
+            which: 2,
+            axis_idx: 3,
+            value: -32768,
+        });
+        round_trip(Event::JoyBallMotion {
+            timestamp: 1,
+            which: 2,
+            ball_idx: 0,
+            xrel: -5,
+            yrel: 5,
+        });
+        round_trip(Event::JoyHatMotion {
+            timestamp: 1,
+            which: 2,
+            hat_idx: 1,
+            state: HatState::Leftdown,
+        });
+        round_trip(Event::JoyButtonDown {
+            timestamp: 1,
+            which: 2,
+            button_idx: 11,
+        });
+        round_trip(Event::JoyButtonUp {
+            timestamp: 1,
+            which: 2,
+            button_idx: 11,
+        });
+        round_trip(Event::JoyDeviceAdded {
+            timestamp: 1,
+            which: 0,
+        });
+        round_trip(Event::JoyDeviceRemoved {
+            timestamp: 1,
+            which: 0,
+        });
+        round_trip(Event::ControllerAxisMotion {
+            timestamp: 1,
+            which: 2,
+            axis: Axis::TriggerRight,
+            value: 32767,
+        });
+        round_trip(Event::ControllerButtonDown {
+            timestamp: 1,
+            which: 2,
+            button: Button::DPadLeft,
+        });
+        round_trip(Event::ControllerButtonUp {
+            timestamp: 1,
+            which: 2,
+            button: Button::Guide,
+        });
+        round_trip(Event::ControllerDeviceAdded {
+            timestamp: 1,
+            which: 0,
+        });
+        round_trip(Event::ControllerDeviceRemoved {
+            timestamp: 1,
+            which: 0,
+        });
+        round_trip(Event::ControllerDeviceRemapped {
+            timestamp: 1,
+            which: 0,
+        });
+    }
+
+    #[test]
+    fn touch_events_round_trip() {
+        round_trip(Event::FingerDown {
+            timestamp: 1,
+            touch_id: -2,
+            finger_id: 3,
+            x: 0.1,
+            y: 0.9,
+            dx: -0.015625,
+            dy: 1e-7,
+            pressure: 1.0,
+        });
+        round_trip(Event::FingerUp {
+            timestamp: 1,
+            touch_id: 2,
+            finger_id: 3,
+            x: 0.5,
+            y: 0.5,
+            dx: 0.0,
+            dy: 0.0,
+            pressure: 0.0,
+        });
+        round_trip(Event::FingerMotion {
+            timestamp: 1,
+            touch_id: 2,
+            finger_id: 3,
+            x: 1.0 / 3.0,
+            y: 2.0 / 3.0,
+            dx: 0.25,
+            dy: -0.25,
+            pressure: 0.75,
+        });
+        round_trip(Event::DollarGesture {
+            timestamp: 1,
+            touch_id: 2,
+            gesture_id: -4,
+            num_fingers: 2,
+            error: 0.125,
+            x: 0.3,
+            y: 0.7,
+        });
+        round_trip(Event::DollarRecord {
+            timestamp: 1,
+            touch_id: 2,
+            gesture_id: 4,
+            num_fingers: 1,
+            error: 0.0,
+            x: 0.3,
+            y: 0.7,
+        });
+        round_trip(Event::MultiGesture {
+            timestamp: 1,
+            touch_id: 2,
+            d_theta: -0.5,
+            d_dist: 0.05,
+            x: 0.4,
+            y: 0.6,
+            num_fingers: 3,
+        });
+    }
+
+    #[test]
+    fn other_events_round_trip() {
+        round_trip(Event::AppTerminating { timestamp: 1 });
+        round_trip(Event::AppLowMemory { timestamp: 1 });
+        round_trip(Event::AppWillEnterBackground { timestamp: 1 });
+        round_trip(Event::AppDidEnterBackground { timestamp: 1 });
+        round_trip(Event::AppWillEnterForeground { timestamp: 1 });
+        round_trip(Event::AppDidEnterForeground { timestamp: 1 });
+        round_trip(Event::ClipboardUpdate { timestamp: 1 });
+        round_trip(Event::DropFile {
+            timestamp: 1,
+            filename: "C:\\games\\save\tfile".to_owned(),
+        });
+        round_trip(Event::Unknown {
+            timestamp: 1,
+            type_: 0x9000,
+        });
+    }
+
+    #[test]
+    fn user_events_are_rejected() {
+        let event = Event::User {
+            timestamp: 1,
+            window_id: 0,
+            type_: 0x8000,
+            code: 0,
+            data1: ::std::ptr::null_mut(),
+            data2: ::std::ptr::null_mut(),
+        };
+        let mut line = Vec::new();
+        assert!(write_event(&mut line, 0, &event).is_err());
+        assert!(line.is_empty());
+    }
+
+    #[test]
+    fn recording_goes_on_after_user_events() {
+        let recorder = InputRecorder::new(BoxedInputMapper::<()>::new(), Vec::new());
+        let user = Event::User {
+            timestamp: 1,
+            window_id: 0,
+            type_: 0x8000,
+            code: 0,
+            data1: ::std::ptr::null_mut(),
+            data2: ::std::ptr::null_mut(),
+        };
+        recorder.handle(&user, &mut |_| {});
+        recorder.handle(&Event::Quit { timestamp: 2 }, &mut |_| {});
+
+        assert_eq!(recorder.skipped(), 1);
+        assert!(recorder.take_error().is_none());
+        let (_, recording) = recorder.into_inner().unwrap();
+        assert_eq!(String::from_utf8(recording).unwrap(), "0\t2\tQuit\n");
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        assert!(parse_event("").is_none());
+        assert!(parse_event("1\t2").is_none());
+        assert!(parse_event("1\t2\tNoSuchEvent").is_none());
+        assert!(parse_event("x\t2\tQuit").is_none());
+        assert!(parse_event("1\t2\tQuit\textra").is_none());
+        assert!(parse_event("1\t2\tJoyHatMotion\t0\t0\t5").is_none());
+        assert!(parse_event("1\t2\tKeyDown\t0\tNo Such Key\t\t0\tfalse").is_none());
+        assert!(read_recording("1\t2\tQuit\n\nbad\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn escape_round_trips() {
+        for text in &["", "plain", "\t", "\n", "\r\n", "\\", "\\t", "a\\\tb\nc\\n", "ünïcödé"] {
+            let escaped = escape(text);
+            assert!(!escaped.contains(['\t', '\n', '\r']));
+            assert_eq!(unescape(&escaped), *text);
+        }
+    }
+
+    #[test]
+    fn unescape_keeps_stray_backslashes() {
+        assert_eq!(unescape("a\\"), "a\\");
+        assert_eq!(unescape("\\x"), "x");
+    }
+}
@@ -1,26 +1,163 @@
 use std::borrow::Cow;
-use std::cell::RefCell;
+use std::cell::{Cell, Ref, RefCell};
 use std::collections::HashMap;
-use std::fmt::{self, Debug};
-use std::path::PathBuf;
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
 
 use sdl2::render::Texture;
+#[cfg(feature = "audio")]
+use sdl2_mixer::{Chunk, Music};
 use sdl2_ttf::{Sdl2TtfContext, Font};
 
 use device::Device;
+use frame::Frame;
+use plugin::Plugin;
+use renderer::Renderer;
 
 // This hack allows us to index the `HashMap` with tuples of unowned strings.
 type FontId = (Cow<'static, str>, u16);
 
+/// An asset shared by everything using it.
+///
+/// The asset can be replaced in place, e.g. when it is reloaded, so
+/// that its users pick up the new version. Users that derive something
+/// from the asset can compare its version to notice the change.
+pub struct Asset<T> {
+    value: RefCell<T>,
+    version: Cell<u32>,
+}
+
+impl<T> Asset<T> {
+    /// Creates an asset at version `0`.
+    pub fn new(value: T) -> Asset<T> {
+        Asset {
+            value: RefCell::new(value),
+            version: Cell::new(0),
+        }
+    }
+
+    /// Borrows the current version of the asset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the asset is being replaced.
+    #[inline]
+    pub fn borrow<'s>(&'s self) -> Ref<'s, T> {
+        self.value.borrow()
+    }
+
+    /// Returns how many times the asset has been replaced.
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version.get()
+    }
+
+    /// Replaces the asset and increases its version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the asset is borrowed.
+    pub fn replace(&self, value: T) {
+        *self.value.borrow_mut() = value;
+        self.version.set(self.version.get().wrapping_add(1));
+    }
+}
+
+impl<T> Debug for Asset<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Asset")
+            .field("version", &self.version.get())
+            .finish()
+    }
+}
+
+/// A cached asset along with where it was loaded from.
+struct Entry<T> {
+    asset: Rc<Asset<T>>,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl<T> Entry<T> {
+    fn new(asset: T, path: PathBuf) -> Entry<T> {
+        Entry {
+            asset: Rc::new(Asset::new(asset)),
+            modified: modified(&path),
+            path: path,
+        }
+    }
+
+    /// Reloads the asset if its file has changed.
+    fn reload<F>(&mut self, load: F) -> Result<(), ReloadError>
+        where F: FnOnce(&Path) -> Result<T, String>
+    {
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return Ok(());
+        }
+        // Only report a failure once per change of the file
+        self.modified = modified;
+        match load(&self.path) {
+            Ok(asset) => {
+                self.asset.replace(asset);
+                Ok(())
+            }
+            Err(message) => {
+                Err(ReloadError {
+                    path: self.path.clone(),
+                    message: message,
+                })
+            }
+        }
+    }
+}
+
+// Derived `Clone` would require `T: Clone`
+impl<T> Clone for Entry<T> {
+    fn clone(&self) -> Entry<T> {
+        Entry {
+            asset: self.asset.clone(),
+            path: self.path.clone(),
+            modified: self.modified,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// A failure to reload a changed asset.
+#[derive(Clone, Debug)]
+pub struct ReloadError {
+    /// The path of the asset.
+    pub path: PathBuf,
+    /// The reason the asset could not be loaded.
+    pub message: String,
+}
+
+impl Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "could not reload {}: {}", self.path.display(), self.message)
+    }
+}
+
 /// A resource manager responsible for loading and caching assets.
 #[derive(Clone)]
 pub struct ResourceManager<'a, 'r: 'a> {
     prefix: PathBuf,
     device: &'a Device<'r>,
     ttf_ctx: &'a Sdl2TtfContext,
-    textures: RefCell<HashMap<String, Rc<Texture>>>,
-    fonts: RefCell<HashMap<FontId, Rc<Font>>>,
+    textures: RefCell<HashMap<String, Entry<Texture>>>,
+    fonts: RefCell<HashMap<FontId, Entry<Font>>>,
+    #[cfg(feature = "audio")]
+    sounds: RefCell<HashMap<String, Rc<Chunk>>>,
+    #[cfg(feature = "audio")]
+    music: RefCell<HashMap<String, Rc<Music>>>,
 }
 
 impl<'a, 'r> ResourceManager<'a, 'r> {
@@ -43,6 +180,10 @@ impl<'a, 'r> ResourceManager<'a, 'r> {
             ttf_ctx: ttf_ctx,
             textures: Default::default(),
             fonts: Default::default(),
+            #[cfg(feature = "audio")]
+            sounds: Default::default(),
+            #[cfg(feature = "audio")]
+            music: Default::default(),
         }
     }
 
@@ -52,19 +193,23 @@ impl<'a, 'r> ResourceManager<'a, 'r> {
     /// returned directly. Otherwise the texture will be loaded from
     /// disk.
     ///
+    /// The texture is shared with every other user of the same path,
+    /// and is replaced in place when it is reloaded.
+    ///
     /// # Panics
     ///
     /// Panics if the texture is not cached, and loading it fails (e.g.
     /// if the file pointed to by path does not exist or is malformed).
-    pub fn texture(&self, path: &str) -> Rc<Texture> {
-        if let Some(texture) = self.textures.borrow().get(path) {
-            return texture.clone();
+    pub fn texture(&self, path: &str) -> Rc<Asset<Texture>> {
+        if let Some(entry) = self.textures.borrow().get(path) {
+            return entry.asset.clone();
         }
         let mut path_buf = self.prefix.clone();
         path_buf.push(path);
         let texture = self.device.load_texture(&path_buf).expect("could not load texture");
-        let texture = Rc::new(texture);
-        self.textures.borrow_mut().insert(path.to_owned(), texture.clone());
+        let entry = Entry::new(texture, path_buf);
+        let texture = entry.asset.clone();
+        self.textures.borrow_mut().insert(path.to_owned(), entry);
         texture
     }
 
@@ -75,29 +220,118 @@ impl<'a, 'r> ResourceManager<'a, 'r> {
     /// loaded from disk. If high-dpi mode is enabled for the renderer,
     /// then the returned font is automatically upscaled appropriately.
     ///
+    /// Like textures, fonts are shared and replaced in place when they
+    /// are reloaded. Labels created with `Label::with_font` follow the
+    /// reloads.
+    ///
     /// # Panics
     ///
     /// Panics if the font is not cached, and loading it fails (e.g.
     /// if the file pointed to by path does not exist or is malformed).
-    pub fn font(&self, path: &str, point_size: u16) -> Rc<Font> {
-        if let Some(font) = self.fonts.borrow().get(&(Cow::Borrowed(path), point_size)) {
-            return font.clone();
+    pub fn font(&self, path: &str, point_size: u16) -> Rc<Asset<Font>> {
+        if let Some(entry) = self.fonts.borrow().get(&(Cow::Borrowed(path), point_size)) {
+            return entry.asset.clone();
         }
+        let mut path_buf = self.prefix.clone();
+        path_buf.push(path);
+        let font = self.load_font(&path_buf, point_size).expect("could not load font");
+        let entry = Entry::new(font, path_buf);
+        let font = entry.asset.clone();
+        self.fonts.borrow_mut().insert((path.to_owned().into(), point_size), entry);
+        font
+    }
+
+    /// Loads a font, upscaled for high-dpi mode.
+    ///
+    /// Fonts are cached under the requested `point_size`, so that they
+    /// are found again, and reloaded, with the same scaling.
+    fn load_font(&self, path: &Path, point_size: u16) -> Result<Font, String> {
         let (sx, sy) = self.device.scale();
         let scale = if sx >= sy {
             sx
         } else {
             sy
         };
+        self.ttf_ctx.load_font(path, (point_size as f32 * scale) as u16)
+    }
 
-        let point_size = (point_size as f32 * scale) as u16;
+    /// Ensures a sound effect is loaded and returns it.
+    ///
+    /// Sound effects are cached like textures, but are not reloaded
+    /// when their file changes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sound is not cached, and loading it fails. Use
+    /// `try_sound` to handle the failure instead.
+    #[cfg(feature = "audio")]
+    pub fn sound(&self, path: &str) -> Rc<Chunk> {
+        self.try_sound(path).expect("could not load sound")
+    }
 
+    /// Ensures a sound effect is loaded and returns it, or the reason
+    /// it could not be loaded.
+    #[cfg(feature = "audio")]
+    pub fn try_sound(&self, path: &str) -> Result<Rc<Chunk>, String> {
+        if let Some(sound) = self.sounds.borrow().get(path) {
+            return Ok(sound.clone());
+        }
         let mut path_buf = self.prefix.clone();
         path_buf.push(path);
-        let font = self.ttf_ctx.load_font(path.as_ref(), point_size).expect("could not load font");
-        let font = Rc::new(font);
-        self.fonts.borrow_mut().insert((path.to_owned().into(), point_size), font.clone());
-        font
+        let sound = Rc::new(Chunk::from_file(&path_buf)?);
+        self.sounds.borrow_mut().insert(path.to_owned(), sound.clone());
+        Ok(sound)
+    }
+
+    /// Ensures a music track is loaded and returns it.
+    ///
+    /// Music is cached like sound effects. Compressed tracks are
+    /// streamed from their file while they play.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the music is not cached, and loading it fails. Use
+    /// `try_music` to handle the failure instead.
+    #[cfg(feature = "audio")]
+    pub fn music(&self, path: &str) -> Rc<Music> {
+        self.try_music(path).expect("could not load music")
+    }
+
+    /// Ensures a music track is loaded and returns it, or the reason
+    /// it could not be loaded.
+    #[cfg(feature = "audio")]
+    pub fn try_music(&self, path: &str) -> Result<Rc<Music>, String> {
+        if let Some(music) = self.music.borrow().get(path) {
+            return Ok(music.clone());
+        }
+        let mut path_buf = self.prefix.clone();
+        path_buf.push(path);
+        let music = Rc::new(Music::from_file(&path_buf)?);
+        self.music.borrow_mut().insert(path.to_owned(), music.clone());
+        Ok(music)
+    }
+
+    /// Reloads every cached texture and font whose file has changed
+    /// since it was loaded.
+    ///
+    /// Assets are replaced in place, so everything sharing them, like
+    /// sprites and labels, uses the new version from then on.
+    ///
+    /// Assets that fail to load keep their old version, and the
+    /// failures are returned.
+    pub fn reload_changed(&self) -> Vec<ReloadError> {
+        let mut errors = Vec::new();
+        for entry in self.textures.borrow_mut().values_mut() {
+            if let Err(err) = entry.reload(|path| self.device.load_texture(path)) {
+                errors.push(err);
+            }
+        }
+        for (&(_, point_size), entry) in self.fonts.borrow_mut().iter_mut() {
+            if let Err(err) = entry.reload(|path| self.load_font(path, point_size)) {
+                errors.push(err);
+            }
+        }
+        errors
     }
 
     /// Returns the renderer this resource manager was created with.
@@ -133,10 +367,83 @@ impl<I> Debug for IterDebug<I>
 
 impl<'a, 'r> Debug for ResourceManager<'a, 'r> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("ResourceManager")
-            .field("textures", &IterDebug(self.textures.borrow().keys()))
-            .field("fonts", &IterDebug(self.fonts.borrow().keys()))
-            .field("renderer", &(..))
-            .finish()
+        let mut debug = f.debug_struct("ResourceManager");
+        debug.field("textures", &IterDebug(self.textures.borrow().keys()))
+            .field("fonts", &IterDebug(self.fonts.borrow().keys()));
+        #[cfg(feature = "audio")]
+        debug.field("sounds", &IterDebug(self.sounds.borrow().keys()))
+            .field("music", &IterDebug(self.music.borrow().keys()));
+        debug.field("renderer", &(..)).finish()
+    }
+}
+
+/// A plugin reloading changed assets of a `ResourceManager` while the
+/// game is running.
+///
+/// The files of the assets are checked at a fixed interval, before a
+/// frame is rendered. Failures are written to stderr by default.
+pub struct HotReload<'m, 'a: 'm, 'r: 'a> {
+    resources: &'m ResourceManager<'a, 'r>,
+    interval: Duration,
+    last_check: Instant,
+    on_error: Box<FnMut(&ReloadError) + 'm>,
+}
+
+impl<'m, 'a, 'r> HotReload<'m, 'a, 'r> {
+    /// Creates a plugin checking the assets of `resources` every time
+    /// `interval` passes.
+    pub fn new(resources: &'m ResourceManager<'a, 'r>, interval: Duration) -> Self {
+        HotReload {
+            resources: resources,
+            interval: interval,
+            last_check: Instant::now(),
+            on_error: Box::new(|err| {
+                let _ = writeln!(io::stderr(), "{}", err);
+            }),
+        }
+    }
+
+    /// Sets the function failures to reload are reported to.
+    pub fn set_error_handler<F>(&mut self, on_error: F)
+        where F: FnMut(&ReloadError) + 'm
+    {
+        self.on_error = Box::new(on_error);
+    }
+}
+
+impl<'m, 'a, 'r, M> Plugin<M> for HotReload<'m, 'a, 'r> {
+    fn before_render(&mut self, _frame: &Frame, _renderer: &mut Renderer) {
+        if self.last_check.elapsed() < self.interval {
+            return;
+        }
+        self.last_check = Instant::now();
+        for err in self.resources.reload_changed() {
+            (self.on_error)(&err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Asset;
+
+    #[test]
+    fn replacing_an_asset_bumps_its_version() {
+        let asset = Asset::new("old");
+        assert_eq!(asset.version(), 0);
+        asset.replace("new");
+        assert_eq!(*asset.borrow(), "new");
+        assert_eq!(asset.version(), 1);
+        asset.replace("newer");
+        assert_eq!(*asset.borrow(), "newer");
+        assert_eq!(asset.version(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn borrowed_assets_cannot_be_replaced() {
+        let asset = Asset::new(1);
+        let _value = asset.borrow();
+        asset.replace(2);
     }
 }
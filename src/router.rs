@@ -0,0 +1,274 @@
+use entities::{Entity, EntityMap};
+use frame::Frame;
+use gameobject::Behavior;
+use queue::Queue;
+use renderer::Renderer;
+
+/// Identifies a group of entities in a `Router`.
+///
+/// The numbering of groups is up to the game.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct GroupId(pub u32);
+
+/// The entities a message is meant for.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Recipient {
+    /// Every entity.
+    All,
+    /// A single entity.
+    Entity(Entity),
+    /// Every entity in a group.
+    Group(GroupId),
+}
+
+/// A message that may be addressed to specific entities.
+pub trait Addressed {
+    /// Returns who the message is for.
+    ///
+    /// Messages are broadcast to every entity by default.
+    fn recipient(&self) -> Recipient {
+        Recipient::All
+    }
+}
+
+struct Entry<B> {
+    groups: Vec<GroupId>,
+    behavior: B,
+}
+
+/// A collection of entities that routes messages to their recipients.
+///
+/// Unlike the slice implementation of `Behavior`, a message addressed
+/// to a single entity is neither cloned nor shown to the others, and
+/// messages for several entities are passed to them by reference.
+pub struct Router<B> {
+    entries: EntityMap<Entry<B>>,
+}
+
+impl<B> Router<B> {
+    /// Creates an empty router.
+    pub fn new() -> Router<B> {
+        Router { entries: EntityMap::new() }
+    }
+
+    /// Adds an entity and returns its id.
+    pub fn add(&mut self, behavior: B) -> Entity {
+        self.add_with(|_| behavior)
+    }
+
+    /// Adds an entity created from its id, and returns the id.
+    ///
+    /// This is useful for entities that need to know their own id, e.g.
+    /// to tell others where to send replies.
+    pub fn add_with<F>(&mut self, create: F) -> Entity
+        where F: FnOnce(Entity) -> B
+    {
+        self.entries.insert_with(|entity| {
+            Entry {
+                groups: Vec::new(),
+                behavior: create(entity),
+            }
+        })
+    }
+
+    /// Removes an entity and returns it.
+    pub fn remove(&mut self, entity: Entity) -> Option<B> {
+        self.entries.remove(entity).map(|entry| entry.behavior)
+    }
+
+    /// Adds an entity to a group.
+    ///
+    /// Returns `false` if there is no such entity.
+    pub fn join(&mut self, entity: Entity, group: GroupId) -> bool {
+        match self.entries.get_mut(entity) {
+            Some(entry) => {
+                if !entry.groups.contains(&group) {
+                    entry.groups.push(group);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes an entity from a group.
+    ///
+    /// Returns `false` if there is no such entity.
+    pub fn leave(&mut self, entity: Entity, group: GroupId) -> bool {
+        match self.entries.get_mut(entity) {
+            Some(entry) => {
+                entry.groups.retain(|&g| g != group);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns whether an entity is in a group.
+    pub fn is_member(&self, entity: Entity, group: GroupId) -> bool {
+        self.entries.get(entity).is_some_and(|entry| entry.groups.contains(&group))
+    }
+
+    /// Returns an entity.
+    pub fn get(&self, entity: Entity) -> Option<&B> {
+        self.entries.get(entity).map(|entry| &entry.behavior)
+    }
+
+    /// Returns an entity.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut B> {
+        self.entries.get_mut(entity).map(|entry| &mut entry.behavior)
+    }
+
+    /// Returns the number of entities.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether there are no entities.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the ids of all entities, in the order they were added.
+    pub fn ids(&self) -> Vec<Entity> {
+        self.entries.ids()
+    }
+}
+
+impl<B> Default for Router<B> {
+    fn default() -> Router<B> {
+        Router::new()
+    }
+}
+
+impl<S, B> Behavior<S> for Router<B>
+    where B: Behavior<S>,
+          B::Message: Addressed
+{
+    type Message = B::Message;
+
+    fn initialize(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
+        self.entries.each_mut(|entry| entry.behavior.initialize(state, frame, queue));
+    }
+
+    fn update(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<Self::Message>) {
+        self.entries.each_mut(|entry| entry.behavior.update(state, frame, queue));
+    }
+
+    fn handle_ref(&mut self,
+                  state: &mut S,
+                  frame: &Frame,
+                  message: &Self::Message,
+                  queue: &mut Queue<Self::Message>) {
+        let recipient = message.recipient();
+        if let Recipient::Entity(entity) = recipient {
+            if let Some(entry) = self.entries.get_mut(entity) {
+                entry.behavior.handle_ref(state, frame, message, queue);
+            }
+            return;
+        }
+
+        self.entries.each_mut(|entry| {
+            let is_recipient = match recipient {
+                Recipient::Group(group) => entry.groups.contains(&group),
+                _ => true,
+            };
+            if is_recipient {
+                entry.behavior.handle_ref(state, frame, message, queue);
+            }
+        });
+    }
+
+    fn render(&mut self, state: &S, frame: &Frame, renderer: &mut Renderer) {
+        self.entries.each_mut(|entry| entry.behavior.render(state, frame, renderer));
+    }
+
+    fn render_window(&mut self, state: &S, frame: &Frame, window_id: u32, renderer: &mut Renderer) {
+        self.entries
+            .each_mut(|entry| entry.behavior.render_window(state, frame, window_id, renderer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Addressed, GroupId, Recipient, Router};
+    use frame::Frame;
+    use gameobject::Behavior;
+    use queue::{Queue, Scheduler};
+
+    struct Message(Recipient);
+
+    impl Addressed for Message {
+        fn recipient(&self) -> Recipient {
+            self.0
+        }
+    }
+
+    /// Records its name in the state when it gets a message.
+    struct Named(&'static str);
+
+    impl Behavior<Vec<&'static str>> for Named {
+        type Message = Message;
+
+        fn handle_ref(&mut self,
+                      state: &mut Vec<&'static str>,
+                      _frame: &Frame,
+                      _message: &Message,
+                      _queue: &mut Queue<Message>) {
+            state.push(self.0);
+        }
+    }
+
+    fn send(router: &mut Router<Named>, recipient: Recipient) -> Vec<&'static str> {
+        let mut state = Vec::new();
+        let mut messages = Vec::new();
+        let mut scheduler = Scheduler::new();
+        router.handle(&mut state,
+                      &Frame::new(),
+                      Message(recipient),
+                      &mut Queue::new(&mut messages, &mut scheduler));
+        state
+    }
+
+    #[test]
+    fn messages_reach_their_recipients() {
+        let mut router = Router::new();
+        let a = router.add(Named("a"));
+        let b = router.add(Named("b"));
+        router.add(Named("c"));
+        router.join(a, GroupId(1));
+        router.join(b, GroupId(1));
+
+        assert_eq!(send(&mut router, Recipient::All), vec!["a", "b", "c"]);
+        assert_eq!(send(&mut router, Recipient::Entity(b)), vec!["b"]);
+        assert_eq!(send(&mut router, Recipient::Group(GroupId(1))), vec!["a", "b"]);
+        assert!(send(&mut router, Recipient::Group(GroupId(2))).is_empty());
+    }
+
+    #[test]
+    fn removed_entities_get_no_messages() {
+        let mut router = Router::new();
+        let a = router.add(Named("a"));
+        router.remove(a);
+        let b = router.add(Named("b"));
+
+        assert!(send(&mut router, Recipient::Entity(a)).is_empty());
+        assert_eq!(send(&mut router, Recipient::Entity(b)), vec!["b"]);
+        assert!(!router.join(a, GroupId(1)));
+        assert!(!router.leave(a, GroupId(1)));
+    }
+
+    #[test]
+    fn leaving_a_group_stops_its_messages() {
+        let mut router = Router::new();
+        let a = router.add(Named("a"));
+        router.join(a, GroupId(1));
+        assert!(router.leave(a, GroupId(1)));
+
+        assert!(!router.is_member(a, GroupId(1)));
+        assert!(send(&mut router, Recipient::Group(GroupId(1))).is_empty());
+        assert_eq!(send(&mut router, Recipient::All), vec!["a"]);
+    }
+}
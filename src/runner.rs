@@ -0,0 +1,235 @@
+use std::mem;
+use std::time::{Duration, Instant};
+
+use event::EventSource;
+use frame::Frame;
+use gameobject::Behavior;
+use input::InputManager;
+use plugin::Plugin;
+use profiler::{FrameTimes, Phase};
+use queue::{Queue, Scheduler};
+use renderer::Renderer;
+use window::Viewport;
+
+/// The message queues, clock and plugins of a running game.
+///
+/// This is the part of the game loop shared by `Game` and `Headless`,
+/// which only differ in where time and events come from.
+pub struct Runner<'a, M> {
+    front: Vec<M>,
+    back: Vec<M>,
+    scheduler: Scheduler<M>,
+    frame: Frame,
+    times: FrameTimes,
+    plugins: Vec<Box<Plugin<M> + 'a>>,
+}
+
+impl<'a, M> Runner<'a, M> {
+    /// Creates a runner for a new game.
+    pub fn new() -> Runner<'a, M> {
+        Runner {
+            front: Vec::new(),
+            back: Vec::new(),
+            scheduler: Scheduler::new(),
+            frame: Frame::new(),
+            times: FrameTimes::new(),
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Adds a plugin to the game loop.
+    pub fn add_plugin(&mut self, plugin: Box<Plugin<M> + 'a>) {
+        self.plugins.push(plugin);
+    }
+
+    /// Starts a new game with empty queues and a fresh frame context.
+    ///
+    /// The plugins are kept.
+    pub fn reset(&mut self) {
+        self.front.clear();
+        self.back.clear();
+        self.scheduler = Scheduler::new();
+        self.frame = Frame::new();
+        self.times = FrameTimes::new();
+    }
+
+    /// Returns the context of the current frame.
+    #[inline]
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// Sets the viewport seen by the behavior.
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.frame.viewport = viewport;
+    }
+
+    /// Initializes the behavior.
+    pub fn initialize<S, B>(&mut self, state: &mut S, behavior: &mut B)
+        where B: Behavior<S, Message = M>
+    {
+        behavior.initialize(state,
+                            &self.frame,
+                            &mut Queue::new(&mut self.front, &mut self.scheduler));
+    }
+
+    /// Turns the pending events into messages.
+    ///
+    /// Events are taken from `events` and turned into messages by
+    /// `manager`, to be handled in the next tick. This should be called
+    /// exactly once per frame, before `tick` or `idle`.
+    pub fn poll<I, E>(&mut self, manager: &I, events: &mut E)
+        where I: InputManager<M>,
+              E: EventSource + ?Sized
+    {
+        let start = Instant::now();
+        for plugin in &mut self.plugins {
+            plugin.before_events(&self.frame);
+        }
+
+        manager.begin_tick(self.frame.tick);
+        let front = &mut self.front;
+        while let Some(event) = events.poll_event(self.frame.tick) {
+            for plugin in &mut self.plugins {
+                plugin.event(&self.frame, &event);
+            }
+            manager.handle(&event, &mut |m| front.push(m));
+        }
+
+        for plugin in &mut self.plugins {
+            plugin.after_events(&self.frame);
+        }
+        self.times.add(Phase::Events, start.elapsed());
+    }
+
+    /// Advances the game by a single tick of length `delta`.
+    ///
+    /// Events are not polled; call `poll` once per frame beforehand.
+    /// Each message is passed to `inspect` before it is handled, and if
+    /// that returns a value, the tick is aborted and the value returned.
+    pub fn tick<S, B, F, R>(&mut self,
+                            state: &mut S,
+                            behavior: &mut B,
+                            delta: Duration,
+                            inspect: F)
+                            -> Option<R>
+        where B: Behavior<S, Message = M>,
+              F: FnMut(&M) -> Option<R>
+    {
+        self.frame.delta = delta;
+        self.frame.elapsed += delta;
+
+        // Handle events and scheduled messages
+        self.scheduler.poll(&self.frame, &mut self.front);
+        if let Some(value) = self.handle_messages(state, behavior, inspect) {
+            return Some(value);
+        }
+
+        // Update the objects and let them send messages
+        let start = Instant::now();
+        for plugin in &mut self.plugins {
+            plugin.before_update(&self.frame);
+        }
+        behavior.update(state,
+                        &self.frame,
+                        &mut Queue::new(&mut self.front, &mut self.scheduler));
+        for plugin in &mut self.plugins {
+            plugin.after_update(&self.frame);
+        }
+        self.times.add(Phase::Update, start.elapsed());
+        self.frame.tick += 1;
+        None
+    }
+
+    /// Handles pending messages without updating the behavior.
+    ///
+    /// This is used while the game is paused. Game time stands still,
+    /// so scheduled messages are not sent either. Like with `tick`,
+    /// events are not polled, and each message is passed to `inspect`
+    /// before it is handled.
+    pub fn idle<S, B, F, R>(&mut self, state: &mut S, behavior: &mut B, inspect: F) -> Option<R>
+        where B: Behavior<S, Message = M>,
+              F: FnMut(&M) -> Option<R>
+    {
+        self.handle_messages(state, behavior, inspect)
+    }
+
+    fn handle_messages<S, B, F, R>(&mut self,
+                                   state: &mut S,
+                                   behavior: &mut B,
+                                   mut inspect: F)
+                                   -> Option<R>
+        where B: Behavior<S, Message = M>,
+              F: FnMut(&M) -> Option<R>
+    {
+        let start = Instant::now();
+        for plugin in &mut self.plugins {
+            plugin.before_messages(&self.frame);
+        }
+
+        // Let the objects handle messages
+        let front = &mut self.front;
+        for m in front.drain(..) {
+            for plugin in &mut self.plugins {
+                plugin.message(&self.frame, &m);
+            }
+            if let Some(value) = inspect(&m) {
+                return Some(value);
+            }
+            behavior.handle(state,
+                            &self.frame,
+                            m,
+                            &mut Queue::new(&mut self.back, &mut self.scheduler));
+        }
+
+        // Swap the message queues
+        mem::swap(front, &mut self.back);
+
+        for plugin in &mut self.plugins {
+            plugin.after_messages(&self.frame);
+        }
+        self.times.add(Phase::Messages, start.elapsed());
+        None
+    }
+
+    /// Renders the behavior with the interpolation factor `alpha`.
+    pub fn render<S, B>(&mut self, state: &S, behavior: &mut B, renderer: &mut Renderer, alpha: f64)
+        where B: Behavior<S, Message = M>
+    {
+        self.frame.alpha = alpha;
+        let start = Instant::now();
+        for plugin in &mut self.plugins {
+            plugin.before_render(&self.frame, renderer);
+        }
+        behavior.render(state, &self.frame, renderer);
+        for plugin in &mut self.plugins {
+            plugin.after_render(&self.frame, renderer);
+        }
+        self.times.add(Phase::Render, start.elapsed());
+    }
+
+    /// Renders the behavior to an additional window.
+    pub fn render_window<S, B>(&mut self,
+                               state: &S,
+                               behavior: &mut B,
+                               window_id: u32,
+                               renderer: &mut Renderer)
+        where B: Behavior<S, Message = M>
+    {
+        let start = Instant::now();
+        behavior.render_window(state, &self.frame, window_id, renderer);
+        self.times.add(Phase::Render, start.elapsed());
+    }
+
+    /// Adds time spent outside the runner to a phase of the current
+    /// frame.
+    pub fn add_time(&mut self, phase: Phase, time: Duration) {
+        self.times.add(phase, time);
+    }
+
+    /// Ends the current frame and returns the time spent in each phase.
+    pub fn end_frame(&mut self) -> FrameTimes {
+        self.frame.number += 1;
+        mem::replace(&mut self.times, FrameTimes::new())
+    }
+}
@@ -0,0 +1,370 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use sdl2::filesystem::{self, PrefPathError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
+
+const EXTENSION: &str = "save";
+const TEMP_EXTENSION: &str = "save.tmp";
+
+/// Game state that can be written to save files.
+///
+/// Save files are tagged with the version of the state they were
+/// written with, so that files from older versions of a game can be
+/// upgraded with `migrate` when they are loaded.
+pub trait Savable: Serialize + DeserializeOwned {
+    /// Returns the current version of the saved state.
+    ///
+    /// Increase this whenever the saved form of the state changes.
+    fn version() -> u32;
+
+    /// Upgrades state saved with `version` to the next version.
+    ///
+    /// When loading an older file, this is called once for each version
+    /// between that of the file and the current one. By default, older
+    /// files cannot be loaded.
+    fn migrate(version: u32, _data: Value) -> Result<Value, SaveError> {
+        Err(SaveError::UnsupportedVersion(version))
+    }
+}
+
+/// An error while saving or loading game state.
+#[derive(Debug)]
+pub enum SaveError {
+    /// The file could not be read or written.
+    Io(io::Error),
+    /// The file is not a valid save file, or its state does not match
+    /// the expected type.
+    Format(serde_json::Error),
+    /// The file was saved with a version that cannot be loaded.
+    UnsupportedVersion(u32),
+    /// A migration failed for the given reason.
+    Migration(String),
+    /// The name of the slot is not allowed.
+    InvalidSlot(String),
+    /// The directory for save files could not be found.
+    NoSaveDir(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SaveError::Io(ref err) => write!(f, "could not access save file: {}", err),
+            SaveError::Format(ref err) => write!(f, "invalid save file: {}", err),
+            SaveError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save file version: {}", version)
+            }
+            SaveError::Migration(ref reason) => {
+                write!(f, "could not migrate save file: {}", reason)
+            }
+            SaveError::InvalidSlot(ref slot) => write!(f, "invalid save slot name: {:?}", slot),
+            SaveError::NoSaveDir(ref reason) => write!(f, "no save directory: {}", reason),
+        }
+    }
+}
+
+impl Error for SaveError {
+    fn description(&self) -> &str {
+        match *self {
+            SaveError::Io(..) => "could not access save file",
+            SaveError::Format(..) => "invalid save file",
+            SaveError::UnsupportedVersion(..) => "unsupported save file version",
+            SaveError::Migration(..) => "could not migrate save file",
+            SaveError::InvalidSlot(..) => "invalid save slot name",
+            SaveError::NoSaveDir(..) => "no save directory",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            SaveError::Io(ref err) => Some(err),
+            SaveError::Format(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> SaveError {
+        SaveError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> SaveError {
+        SaveError::Format(err)
+    }
+}
+
+#[derive(Serialize)]
+struct SaveFile<'a, T: 'a> {
+    version: u32,
+    state: &'a T,
+}
+
+#[derive(Deserialize)]
+struct RawSaveFile {
+    version: u32,
+    state: Value,
+}
+
+/// Writes a snapshot of `state` to `writer`.
+pub fn write_save<T, W>(writer: W, state: &T) -> Result<(), SaveError>
+    where T: Savable,
+          W: Write
+{
+    let file = SaveFile {
+        version: T::version(),
+        state: state,
+    };
+    serde_json::to_writer(writer, &file)?;
+    Ok(())
+}
+
+/// Reads a snapshot of a state from `reader`, migrating it from older
+/// versions if needed.
+pub fn read_save<T, R>(reader: R) -> Result<T, SaveError>
+    where T: Savable,
+          R: Read
+{
+    let RawSaveFile { mut version, mut state } = serde_json::from_reader(reader)?;
+    if version > T::version() {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    while version < T::version() {
+        state = T::migrate(version, state)?;
+        version += 1;
+    }
+    Ok(serde_json::from_value(state)?)
+}
+
+/// Flushes the entries of a directory to disk, so that a renamed file
+/// survives a crash.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened as files on other platforms, where
+/// renames are made durable by the file system instead.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// A directory of save files, stored in named slots.
+///
+/// Slots are written atomically: the state is first written to a
+/// temporary file, which then replaces the slot, so a crash while saving
+/// leaves the previous save of the slot intact.
+pub struct SaveSlots {
+    dir: PathBuf,
+}
+
+impl SaveSlots {
+    /// Uses `dir` for save files, creating it if needed.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Result<SaveSlots, SaveError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(SaveSlots { dir: dir })
+    }
+
+    /// Uses the directory for per-user data of the given application.
+    ///
+    /// The location depends on the platform, e.g.
+    /// `~/.local/share/<org>/<app>` on Linux.
+    pub fn for_user(org: &str, app: &str) -> Result<SaveSlots, SaveError> {
+        let dir = filesystem::pref_path(org, app).map_err(|err| {
+            SaveError::NoSaveDir(match err {
+                PrefPathError::InvalidOrganizationName(..) => "invalid organization name".into(),
+                PrefPathError::InvalidApplicationName(..) => "invalid application name".into(),
+                PrefPathError::SdlError(message) => message,
+            })
+        })?;
+        SaveSlots::new(dir)
+    }
+
+    /// Returns the directory of the save files.
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path of the file for a slot.
+    ///
+    /// Slot names may only contain letters, digits, `-` and `_`.
+    pub fn path(&self, slot: &str) -> Result<PathBuf, SaveError> {
+        let is_valid = !slot.is_empty() &&
+                       slot.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !is_valid {
+            return Err(SaveError::InvalidSlot(slot.to_owned()));
+        }
+        Ok(self.dir.join(slot).with_extension(EXTENSION))
+    }
+
+    /// Saves `state` to a slot, replacing any previous save.
+    pub fn save<T: Savable>(&self, slot: &str, state: &T) -> Result<(), SaveError> {
+        let path = self.path(slot)?;
+        let temp_path = path.with_extension(TEMP_EXTENSION);
+        {
+            let mut writer = BufWriter::new(File::create(&temp_path)?);
+            write_save(&mut writer, state)?;
+            let file = writer.into_inner().map_err(|err| err.into_error())?;
+            file.sync_all()?;
+        }
+        fs::rename(&temp_path, &path)?;
+        sync_dir(&self.dir)?;
+        Ok(())
+    }
+
+    /// Loads the state saved in a slot.
+    pub fn load<T: Savable>(&self, slot: &str) -> Result<T, SaveError> {
+        let file = File::open(self.path(slot)?)?;
+        read_save(BufReader::new(file))
+    }
+
+    /// Returns whether something is saved in a slot.
+    pub fn exists(&self, slot: &str) -> bool {
+        self.path(slot).is_ok_and(|path| path.is_file())
+    }
+
+    /// Deletes the save in a slot.
+    pub fn delete(&self, slot: &str) -> Result<(), SaveError> {
+        fs::remove_file(self.path(slot)?)?;
+        Ok(())
+    }
+
+    /// Returns the names of all slots with a save, in sorted order.
+    pub fn slots(&self) -> Result<Vec<String>, SaveError> {
+        let mut slots = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != EXTENSION) {
+                continue;
+            }
+            if let Some(slot) = path.file_stem().and_then(|stem| stem.to_str()) {
+                slots.push(slot.to_owned());
+            }
+        }
+        slots.sort();
+        Ok(slots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use serde_json::{self, Value};
+
+    use super::*;
+
+    /// The first version of the state, with a single score.
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Old {
+        score: u32,
+    }
+
+    impl Savable for Old {
+        fn version() -> u32 {
+            1
+        }
+    }
+
+    /// The second version of the state, with a score per player.
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct New {
+        scores: Vec<u32>,
+    }
+
+    impl Savable for New {
+        fn version() -> u32 {
+            2
+        }
+
+        fn migrate(version: u32, data: Value) -> Result<Value, SaveError> {
+            match version {
+                1 => {
+                    let old: Old = serde_json::from_value(data)?;
+                    Ok(serde_json::to_value(New { scores: vec![old.score] })?)
+                }
+                _ => Err(SaveError::UnsupportedVersion(version)),
+            }
+        }
+    }
+
+    fn saved<T: Savable>(state: &T) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_save(&mut bytes, state).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn reads_current_version() {
+        let state = New { scores: vec![1, 2] };
+        assert_eq!(read_save::<New, _>(&saved(&state)[..]).unwrap(), state);
+    }
+
+    #[test]
+    fn migrates_older_version() {
+        let bytes = saved(&Old { score: 3 });
+        assert_eq!(read_save::<New, _>(&bytes[..]).unwrap(), New { scores: vec![3] });
+    }
+
+    #[test]
+    fn rejects_older_version_without_migration() {
+        let bytes = br#"{"version":0,"state":{"score":3}}"#;
+        match read_save::<Old, _>(&bytes[..]) {
+            Err(SaveError::UnsupportedVersion(0)) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let bytes = saved(&New { scores: vec![3] });
+        match read_save::<Old, _>(&bytes[..]) {
+            Err(SaveError::UnsupportedVersion(2)) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_input() {
+        let inputs: &[&[u8]] = &[b"",
+                                 b"{\"version\":2,",
+                                 b"{\"state\":{\"scores\":[]}}",
+                                 b"{\"version\":2,\"state\":{\"score\":3}}",
+                                 b"\xff\xfe"];
+        for input in inputs {
+            match read_save::<New, _>(*input) {
+                Err(SaveError::Format(..)) => {}
+                result => panic!("unexpected result for {:?}: {:?}", input, result),
+            }
+        }
+    }
+
+    #[test]
+    fn slots_replace_previous_saves() {
+        let dir = env::temp_dir().join(format!("glorious-save-test-{}", ::std::process::id()));
+        let slots = SaveSlots::new(dir.clone()).unwrap();
+        slots.save("first", &New { scores: vec![1] }).unwrap();
+        slots.save("first", &New { scores: vec![2] }).unwrap();
+        slots.save("second", &New { scores: vec![] }).unwrap();
+        assert_eq!(slots.load::<New>("first").unwrap(), New { scores: vec![2] });
+        assert_eq!(slots.slots().unwrap(), vec!["first", "second"]);
+        slots.delete("second").unwrap();
+        assert!(!slots.exists("second"));
+        match slots.save("../escape", &New { scores: vec![] }) {
+            Err(SaveError::InvalidSlot(..)) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
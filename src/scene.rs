@@ -0,0 +1,292 @@
+use std::io::{self, Write};
+
+use frame::Frame;
+use gameobject::Behavior;
+use queue::Queue;
+use renderer::Renderer;
+
+/// A self-contained part of a game, like a menu or a level.
+///
+/// Scenes are managed by a `SceneStack`, where only the topmost scene
+/// is updated and receives messages.
+pub trait Scene<S>: Behavior<S> {
+    /// Returns whether the scene beneath this one should be rendered
+    /// as well, e.g. for a pause menu drawn on top of the game.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// Tears down the scene when it is removed from the stack.
+    fn teardown(&mut self, _state: &mut S, _frame: &Frame, _queue: &mut Queue<Self::Message>) {
+        // Do nothing by default
+    }
+}
+
+/// A change to the scenes of a `SceneStack`.
+pub enum Transition<S, M> {
+    /// Puts a new scene on top of the current one.
+    Push(Box<Scene<S, Message = M>>),
+    /// Removes the topmost scene.
+    Pop,
+    /// Replaces the topmost scene with a new one.
+    Replace(Box<Scene<S, Message = M>>),
+}
+
+/// A message type that can carry scene transitions.
+pub trait SceneMessage<S>: Sized {
+    /// Returns the transition in this message, or the message itself,
+    /// if it is not a transition.
+    fn into_transition(self) -> Result<Transition<S, Self>, Self>;
+
+    /// Returns whether this message is a transition.
+    fn is_transition(&self) -> bool;
+}
+
+/// A stack of scenes driven by transition messages.
+///
+/// New scenes are initialized when they are pushed, and torn down when
+/// they are popped or replaced.
+///
+/// As transitions carry the new scenes, the stack has to own them, so
+/// transitions are only applied when they are passed to `handle`. Keep
+/// the stack at the top of the game, or as the only child of wrappers
+/// like `Box`, `Mapped` or an enum, and not in a composite that passes
+/// messages on by reference. Transitions passed to `handle_ref` are
+/// reported on stderr and ignored.
+pub struct SceneStack<S, M> {
+    scenes: Vec<Box<Scene<S, Message = M>>>,
+}
+
+impl<S, M> SceneStack<S, M> {
+    /// Creates a new scene stack with `scene` at the bottom.
+    ///
+    /// The scene is initialized along with the stack.
+    pub fn new(scene: Box<Scene<S, Message = M>>) -> SceneStack<S, M> {
+        SceneStack { scenes: vec![scene] }
+    }
+
+    /// Returns the number of scenes on the stack.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Returns whether all scenes have been popped.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    fn visible_scenes(&mut self) -> &mut [Box<Scene<S, Message = M>>] {
+        if self.scenes.is_empty() {
+            return &mut [];
+        }
+        let mut bottom = self.scenes.len() - 1;
+        while bottom > 0 && self.scenes[bottom].is_overlay() {
+            bottom -= 1;
+        }
+        &mut self.scenes[bottom..]
+    }
+
+    fn push(&mut self,
+            state: &mut S,
+            frame: &Frame,
+            mut scene: Box<Scene<S, Message = M>>,
+            queue: &mut Queue<M>) {
+        scene.initialize(state, frame, queue);
+        self.scenes.push(scene);
+    }
+
+    fn pop(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<M>) {
+        if let Some(mut scene) = self.scenes.pop() {
+            scene.teardown(state, frame, queue);
+        }
+    }
+}
+
+impl<S, M> Behavior<S> for SceneStack<S, M>
+    where M: SceneMessage<S>
+{
+    type Message = M;
+
+    fn initialize(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<M>) {
+        for scene in &mut self.scenes {
+            scene.initialize(state, frame, queue);
+        }
+    }
+
+    fn update(&mut self, state: &mut S, frame: &Frame, queue: &mut Queue<M>) {
+        if let Some(scene) = self.scenes.last_mut() {
+            scene.update(state, frame, queue);
+        }
+    }
+
+    fn handle(&mut self, state: &mut S, frame: &Frame, message: M, queue: &mut Queue<M>) {
+        match message.into_transition() {
+            Ok(Transition::Push(scene)) => self.push(state, frame, scene, queue),
+            Ok(Transition::Pop) => self.pop(state, frame, queue),
+            Ok(Transition::Replace(scene)) => {
+                self.pop(state, frame, queue);
+                self.push(state, frame, scene, queue);
+            }
+            Err(message) => {
+                if let Some(scene) = self.scenes.last_mut() {
+                    scene.handle(state, frame, message, queue);
+                }
+            }
+        }
+    }
+
+    fn handle_ref(&mut self, state: &mut S, frame: &Frame, message: &M, queue: &mut Queue<M>) {
+        if message.is_transition() {
+            let _ = writeln!(io::stderr(), "ignoring a scene transition passed by reference");
+            return;
+        }
+        if let Some(scene) = self.scenes.last_mut() {
+            scene.handle_ref(state, frame, message, queue);
+        }
+    }
+
+    fn render(&mut self, state: &S, frame: &Frame, renderer: &mut Renderer) {
+        for scene in self.visible_scenes() {
+            scene.render(state, frame, renderer);
+        }
+    }
+
+    fn render_window(&mut self, state: &S, frame: &Frame, window_id: u32, renderer: &mut Renderer) {
+        for scene in self.visible_scenes() {
+            scene.render_window(state, frame, window_id, renderer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scene, SceneMessage, SceneStack, Transition};
+    use frame::Frame;
+    use gameobject::Behavior;
+    use queue::{Queue, Scheduler};
+
+    type Log = Vec<String>;
+
+    enum Message {
+        Transition(Transition<Log, Message>),
+        Text(&'static str),
+    }
+
+    impl SceneMessage<Log> for Message {
+        fn into_transition(self) -> Result<Transition<Log, Message>, Message> {
+            match self {
+                Message::Transition(transition) => Ok(transition),
+                message => Err(message),
+            }
+        }
+
+        fn is_transition(&self) -> bool {
+            match *self {
+                Message::Transition(..) => true,
+                Message::Text(..) => false,
+            }
+        }
+    }
+
+    /// Records the calls it gets in the log.
+    struct Recorder(&'static str);
+
+    impl Behavior<Log> for Recorder {
+        type Message = Message;
+
+        fn initialize(&mut self, log: &mut Log, _frame: &Frame, _queue: &mut Queue<Message>) {
+            log.push(format!("{} initialize", self.0));
+        }
+
+        fn update(&mut self, log: &mut Log, _frame: &Frame, _queue: &mut Queue<Message>) {
+            log.push(format!("{} update", self.0));
+        }
+
+        fn handle_ref(&mut self,
+                      log: &mut Log,
+                      _frame: &Frame,
+                      message: &Message,
+                      _queue: &mut Queue<Message>) {
+            if let Message::Text(text) = *message {
+                log.push(format!("{} handle {}", self.0, text));
+            }
+        }
+    }
+
+    impl Scene<Log> for Recorder {
+        fn teardown(&mut self, log: &mut Log, _frame: &Frame, _queue: &mut Queue<Message>) {
+            log.push(format!("{} teardown", self.0));
+        }
+    }
+
+    fn push(name: &'static str) -> Message {
+        Message::Transition(Transition::Push(Box::new(Recorder(name))))
+    }
+
+    fn replace(name: &'static str) -> Message {
+        Message::Transition(Transition::Replace(Box::new(Recorder(name))))
+    }
+
+    /// Sends each message to the stack, and updates it afterwards.
+    fn run(stack: &mut SceneStack<Log, Message>, messages: Vec<Message>) -> Log {
+        let mut log = Vec::new();
+        let mut sent = Vec::new();
+        let mut scheduler = Scheduler::new();
+        let frame = Frame::new();
+        let mut queue = Queue::new(&mut sent, &mut scheduler);
+        for message in messages {
+            stack.handle(&mut log, &frame, message, &mut queue);
+        }
+        stack.update(&mut log, &frame, &mut queue);
+        log
+    }
+
+    #[test]
+    fn push_initializes_the_new_top_scene() {
+        let mut stack = SceneStack::new(Box::new(Recorder("menu")));
+        assert_eq!(run(&mut stack, vec![push("level")]),
+                   vec!["level initialize", "level update"]);
+        assert_eq!(stack.len(), 2);
+    }
+
+    #[test]
+    fn pop_tears_down_the_top_scene() {
+        let mut stack = SceneStack::new(Box::new(Recorder("menu")));
+        run(&mut stack, vec![push("level")]);
+        let pop = Message::Transition(Transition::Pop);
+        assert_eq!(run(&mut stack, vec![pop]), vec!["level teardown", "menu update"]);
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn replace_swaps_the_top_scene() {
+        let mut stack = SceneStack::new(Box::new(Recorder("menu")));
+        assert_eq!(run(&mut stack, vec![replace("level")]),
+                   vec!["menu teardown", "level initialize", "level update"]);
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn only_the_top_scene_is_updated_and_handles_messages() {
+        let mut stack = SceneStack::new(Box::new(Recorder("level")));
+        run(&mut stack, vec![push("pause")]);
+        assert_eq!(run(&mut stack, vec![Message::Text("hello")]),
+                   vec!["pause handle hello", "pause update"]);
+    }
+
+    #[test]
+    fn transitions_passed_by_reference_are_ignored() {
+        let mut stack = SceneStack::new(Box::new(Recorder("menu")));
+        let mut log = Vec::new();
+        let mut sent = Vec::new();
+        let mut scheduler = Scheduler::new();
+        stack.handle_ref(&mut log,
+                         &Frame::new(),
+                         &push("level"),
+                         &mut Queue::new(&mut sent, &mut scheduler));
+        assert!(log.is_empty());
+        assert_eq!(stack.len(), 1);
+    }
+}
@@ -4,13 +4,18 @@ use sdl2::render::{Texture, TextureQuery};
 
 use renderer::Renderer;
 use rect::Rect;
+use resources::Asset;
 
 /// A rectangular section of a texture rendered on its own.
+///
+/// The texture is shared, so sprites pick up textures that are reloaded
+/// in place, e.g. by `ResourceManager::reload_changed`.
 #[derive(Clone)]
 pub struct Sprite {
-    /// The area in the texture for this sprite.
-    pub rect: Rect,
-    texture: Rc<Texture>,
+    /// The area in the texture for this sprite, or `None` for the
+    /// entire texture.
+    pub rect: Option<Rect>,
+    texture: Rc<Asset<Texture>>,
 }
 
 impl Sprite {
@@ -18,17 +23,24 @@ impl Sprite {
     ///
     /// The sprite corresponds to the section of `texture` specified by
     /// `rect`, or the entire texture, if `rect` is `None`.
-    pub fn new(texture: Rc<Texture>, rect: Option<Rect>) -> Sprite {
-        let rect = rect.unwrap_or_else(|| {
-            let TextureQuery { width, height, .. } = texture.query();
-            Rect::new(0, 0, width, height)
-        });
+    pub fn new(texture: Rc<Asset<Texture>>, rect: Option<Rect>) -> Sprite {
         Sprite {
             texture: texture,
             rect: rect,
         }
     }
 
+    /// Returns the area in the texture for this sprite.
+    ///
+    /// For a sprite of the entire texture, this is read from the current
+    /// texture, so that it follows reloads that change the size.
+    pub fn area(&self) -> Rect {
+        self.rect.unwrap_or_else(|| {
+            let TextureQuery { width, height, .. } = self.texture.borrow().query();
+            Rect::new(0, 0, width, height)
+        })
+    }
+
     /// Renders the sprite to the renderer at a given point.
     ///
     /// If `size` is `Some`, the sprite will be scaled to that size.
@@ -38,13 +50,14 @@ impl Sprite {
     /// Panics if drawing fails for any reason (e.g. driver failure), or
     /// if the provided texture does not belong to the renderer.
     pub fn render(&self, renderer: &mut Renderer, x: i32, y: i32, size: Option<(u32, u32)>) {
-        let (w, h) = size.unwrap_or_else(|| (self.rect.width, self.rect.height));
+        let area = self.area();
+        let (w, h) = size.unwrap_or_else(|| (area.width, area.height));
         let dst = Rect::new(x, y, w, h);
-        renderer.copy(&*self.texture, Some(self.rect), Some(dst));
+        renderer.copy(&self.texture.borrow(), Some(area), Some(dst));
     }
 
     pub fn render_rect<R: Into<Rect>>(&self, renderer: &mut Renderer, rect: R) {
-        renderer.copy(&*self.texture, Some(self.rect), Some(rect.into()));
+        renderer.copy(&self.texture.borrow(), Some(self.area()), Some(rect.into()));
     }
 }
 
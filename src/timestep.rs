@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+
+/// The longest stretch of time a single frame may feed into the
+/// accumulator.
+///
+/// This keeps a long stall (e.g. a debugger break) from queueing up
+/// hundreds of ticks that the game would then struggle to catch up on.
+const MAX_FRAME_TIME_MS: u64 = 250;
+
+/// The highest supported tick rate, at which a tick lasts a nanosecond.
+pub const MAX_TICK_RATE: u32 = 1_000_000_000;
+
+/// An accumulator running the simulation at a fixed tick rate.
+///
+/// Real time is fed into the accumulator each frame, and is consumed
+/// in steps of exactly one tick. What is left over is exposed as an
+/// interpolation factor, so that rendering can blend between the two
+/// most recent simulation states.
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    /// Creates a new fixed timestep.
+    ///
+    /// The simulation will advance `tick_rate` times per second,
+    /// regardless of the frame rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick_rate` is zero or above `MAX_TICK_RATE`.
+    pub fn new(tick_rate: u32) -> FixedTimestep {
+        assert!(tick_rate > 0 && tick_rate <= MAX_TICK_RATE,
+                "tick rate must be between 1 and {}, got {}",
+                MAX_TICK_RATE,
+                tick_rate);
+        FixedTimestep {
+            step: Duration::new(0, MAX_TICK_RATE / tick_rate),
+            accumulator: Duration::new(0, 0),
+        }
+    }
+
+    /// Returns the length of a single tick.
+    #[inline]
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Resets the timestep, discarding any accumulated time.
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::new(0, 0);
+    }
+
+    /// Accumulates `elapsed`, the length of the last frame, and returns
+    /// the number of ticks to run this frame.
+    pub fn advance_by(&mut self, elapsed: Duration) -> u32 {
+        let max_elapsed = Duration::from_millis(MAX_FRAME_TIME_MS);
+        self.accumulator += if elapsed > max_elapsed {
+            max_elapsed
+        } else {
+            elapsed
+        };
+        let step = self.step.as_nanos() as u64;
+        let accumulated = self.accumulator.as_nanos() as u64;
+        self.accumulator = Duration::from_nanos(accumulated % step);
+        (accumulated / step) as u32
+    }
+
+    /// Returns how far the accumulator has progressed towards the next
+    /// tick, in the range `[0, 1)`.
+    pub fn alpha(&self) -> f64 {
+        self.accumulator.div_duration_f64(self.step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::FixedTimestep;
+
+    fn millis(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn advance_by_runs_whole_ticks() {
+        let mut timestep = FixedTimestep::new(100);
+        assert_eq!(timestep.advance_by(millis(5)), 0);
+        assert_eq!(timestep.advance_by(millis(5)), 1);
+        assert_eq!(timestep.advance_by(millis(25)), 2);
+        assert_eq!(timestep.advance_by(millis(5)), 1);
+    }
+
+    #[test]
+    fn advance_by_caps_long_frames() {
+        let mut timestep = FixedTimestep::new(100);
+        assert_eq!(timestep.advance_by(Duration::from_secs(10)), 25);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn alpha_is_progress_towards_next_tick() {
+        let mut timestep = FixedTimestep::new(100);
+        assert_eq!(timestep.alpha(), 0.0);
+        timestep.advance_by(millis(2));
+        assert!((timestep.alpha() - 0.2).abs() < 1e-9);
+        timestep.advance_by(millis(15));
+        assert!((timestep.alpha() - 0.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn highest_tick_rate_has_nanosecond_ticks() {
+        let mut timestep = FixedTimestep::new(super::MAX_TICK_RATE);
+        assert_eq!(timestep.step(), Duration::new(0, 1));
+        assert_eq!(timestep.advance_by(Duration::new(0, 1000)), 1000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_tick_rate_is_rejected() {
+        FixedTimestep::new(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_high_tick_rate_is_rejected() {
+        FixedTimestep::new(super::MAX_TICK_RATE + 1);
+    }
+}
@@ -0,0 +1,366 @@
+use sdl2::event::{Event, WindowEventId};
+
+use device::Device;
+use event::EventSource;
+use rect::Rect;
+
+/// A change to the state of a window.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum WindowEvent {
+    /// The window was resized to the given size in pixels.
+    Resized {
+        window_id: u32,
+        width: u32,
+        height: u32,
+    },
+    /// The window gained keyboard focus.
+    FocusGained { window_id: u32 },
+    /// The window lost keyboard focus.
+    FocusLost { window_id: u32 },
+    /// The window was minimized or hidden.
+    Minimized { window_id: u32 },
+    /// The window was restored or shown again.
+    Restored { window_id: u32 },
+    /// The user asked for the window to be closed.
+    CloseRequested { window_id: u32 },
+}
+
+impl WindowEvent {
+    /// Returns the window event described by an SDL2 event, if any.
+    pub fn from_event(event: &Event) -> Option<WindowEvent> {
+        let (window_id, id, data1, data2) = match *event {
+            Event::Window { window_id, win_event_id, data1, data2, .. } => {
+                (window_id, win_event_id, data1, data2)
+            }
+            _ => return None,
+        };
+        Some(match id {
+            // Sent for every change of size, unlike `Resized`
+            WindowEventId::SizeChanged => {
+                WindowEvent::Resized {
+                    window_id: window_id,
+                    width: data1 as u32,
+                    height: data2 as u32,
+                }
+            }
+            WindowEventId::FocusGained => WindowEvent::FocusGained { window_id: window_id },
+            WindowEventId::FocusLost => WindowEvent::FocusLost { window_id: window_id },
+            WindowEventId::Minimized |
+            WindowEventId::Hidden => WindowEvent::Minimized { window_id: window_id },
+            WindowEventId::Restored |
+            WindowEventId::Shown => WindowEvent::Restored { window_id: window_id },
+            WindowEventId::Close => WindowEvent::CloseRequested { window_id: window_id },
+            _ => return None,
+        })
+    }
+
+    /// Returns the id of the window the event belongs to.
+    pub fn window_id(&self) -> u32 {
+        match *self {
+            WindowEvent::Resized { window_id, .. } |
+            WindowEvent::FocusGained { window_id } |
+            WindowEvent::FocusLost { window_id } |
+            WindowEvent::Minimized { window_id } |
+            WindowEvent::Restored { window_id } |
+            WindowEvent::CloseRequested { window_id } => window_id,
+        }
+    }
+}
+
+/// The size and scaling of the area a game renders to.
+#[derive(Clone, Copy, Debug)]
+pub struct Viewport {
+    /// The size of the output in pixels.
+    pub output_size: (u32, u32),
+    /// The size of the logical coordinate system used for rendering.
+    ///
+    /// This is the output size, unless a logical size has been set on
+    /// the renderer.
+    pub logical_size: (u32, u32),
+    /// The factors logical coordinates are scaled by.
+    pub scale: (f32, f32),
+    /// The area of the logical coordinate system that is rendered to.
+    pub rect: Rect,
+}
+
+impl Viewport {
+    /// Creates an empty viewport.
+    pub fn new() -> Viewport {
+        Viewport {
+            output_size: (0, 0),
+            logical_size: (0, 0),
+            scale: (1.0, 1.0),
+            rect: Rect::new(0, 0, 0, 0),
+        }
+    }
+
+    /// Returns the current viewport of a device.
+    pub fn of(device: &Device) -> Viewport {
+        let output_size = device.output_size().unwrap_or((0, 0));
+        let logical_size = match device.logical_size() {
+            (0, 0) => output_size,
+            size => size,
+        };
+        Viewport {
+            output_size: output_size,
+            logical_size: logical_size,
+            scale: device.scale(),
+            rect: device.viewport(),
+        }
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Viewport {
+        Viewport::new()
+    }
+}
+
+/// A policy for pausing a game while its window is not in use.
+///
+/// While paused, messages are still handled, so that the game can
+/// still be closed, but `Behavior::update` is not called and game time
+/// stands still.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AutoPause {
+    paused_fps: u32,
+    on_focus_loss: bool,
+}
+
+impl AutoPause {
+    /// Creates a policy pausing a minimized or hidden window at
+    /// `paused_fps` frames per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `paused_fps` is zero.
+    pub fn new(paused_fps: u32) -> AutoPause {
+        assert!(paused_fps > 0, "the paused frame rate must not be zero");
+        AutoPause {
+            paused_fps: paused_fps,
+            on_focus_loss: false,
+        }
+    }
+
+    /// Creates a policy that also pauses the game when the window loses
+    /// focus.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `paused_fps` is zero.
+    pub fn with_focus_loss(paused_fps: u32) -> AutoPause {
+        AutoPause { on_focus_loss: true, ..AutoPause::new(paused_fps) }
+    }
+
+    /// Returns the frame rate the game is limited to while paused.
+    #[inline]
+    pub fn paused_fps(&self) -> u32 {
+        self.paused_fps
+    }
+
+    /// Returns whether the game is also paused when the window loses
+    /// focus.
+    #[inline]
+    pub fn on_focus_loss(&self) -> bool {
+        self.on_focus_loss
+    }
+}
+
+/// The state of a window, as seen through its events.
+#[derive(Clone, Copy, Debug)]
+pub struct WindowState {
+    window_id: u32,
+    hidden: bool,
+    focused: bool,
+    resized: bool,
+}
+
+impl WindowState {
+    /// Creates the state of a visible, focused window.
+    pub fn new(window_id: u32) -> WindowState {
+        WindowState {
+            window_id: window_id,
+            hidden: false,
+            focused: true,
+            resized: false,
+        }
+    }
+
+    /// Returns whether the window should be paused under `policy`.
+    pub fn is_paused(&self, policy: &AutoPause) -> bool {
+        self.hidden || (policy.on_focus_loss && !self.focused)
+    }
+
+    /// Returns whether the window was hidden, i.e. should not be
+    /// rendered.
+    #[inline]
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Returns whether the window was resized since the last call.
+    pub fn take_resized(&mut self) -> bool {
+        let resized = self.resized;
+        self.resized = false;
+        resized
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if event.window_id() != self.window_id {
+            return;
+        }
+        match *event {
+            WindowEvent::Resized { .. } => self.resized = true,
+            WindowEvent::FocusGained { .. } => self.focused = true,
+            WindowEvent::FocusLost { .. } => self.focused = false,
+            WindowEvent::Minimized { .. } => self.hidden = true,
+            WindowEvent::Restored { .. } => self.hidden = false,
+            WindowEvent::CloseRequested { .. } => {}
+        }
+    }
+}
+
+/// An event source keeping track of the state of a window while
+/// passing all events on.
+pub struct Watch<'a, E: ?Sized + 'a> {
+    state: &'a mut WindowState,
+    events: &'a mut E,
+}
+
+impl<'a, E: ?Sized> Watch<'a, E> {
+    /// Creates a new event source watching the window events of
+    /// `events`.
+    pub fn new(state: &'a mut WindowState, events: &'a mut E) -> Watch<'a, E> {
+        Watch {
+            state: state,
+            events: events,
+        }
+    }
+}
+
+impl<'a, E> EventSource for Watch<'a, E>
+    where E: EventSource + ?Sized
+{
+    fn poll_event(&mut self, tick: u64) -> Option<Event> {
+        let event = self.events.poll_event(tick);
+        if let Some(window_event) = event.as_ref().and_then(WindowEvent::from_event) {
+            self.state.update(&window_event);
+        }
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sdl2::event::{Event, WindowEventId};
+
+    use event::{EventSource, ScriptedEvents};
+
+    use super::{AutoPause, Watch, WindowEvent, WindowState};
+
+    fn window(window_id: u32, id: WindowEventId, data1: i32, data2: i32) -> Event {
+        Event::Window {
+            timestamp: 0,
+            window_id: window_id,
+            win_event_id: id,
+            data1: data1,
+            data2: data2,
+        }
+    }
+
+    fn from(id: WindowEventId) -> Option<WindowEvent> {
+        WindowEvent::from_event(&window(1, id, 0, 0))
+    }
+
+    #[test]
+    fn sdl_events_map_to_window_events() {
+        assert_eq!(WindowEvent::from_event(&window(1, WindowEventId::SizeChanged, 640, 480)),
+                   Some(WindowEvent::Resized {
+                       window_id: 1,
+                       width: 640,
+                       height: 480,
+                   }));
+        assert_eq!(from(WindowEventId::FocusGained),
+                   Some(WindowEvent::FocusGained { window_id: 1 }));
+        assert_eq!(from(WindowEventId::FocusLost),
+                   Some(WindowEvent::FocusLost { window_id: 1 }));
+        assert_eq!(from(WindowEventId::Minimized),
+                   Some(WindowEvent::Minimized { window_id: 1 }));
+        assert_eq!(from(WindowEventId::Hidden),
+                   Some(WindowEvent::Minimized { window_id: 1 }));
+        assert_eq!(from(WindowEventId::Restored),
+                   Some(WindowEvent::Restored { window_id: 1 }));
+        assert_eq!(from(WindowEventId::Shown),
+                   Some(WindowEvent::Restored { window_id: 1 }));
+        assert_eq!(from(WindowEventId::Close),
+                   Some(WindowEvent::CloseRequested { window_id: 1 }));
+    }
+
+    #[test]
+    fn other_events_are_not_window_events() {
+        assert_eq!(from(WindowEventId::Resized), None);
+        assert_eq!(from(WindowEventId::Moved), None);
+        assert_eq!(from(WindowEventId::Enter), None);
+        assert_eq!(WindowEvent::from_event(&Event::Quit { timestamp: 0 }), None);
+    }
+
+    /// Passes `events` through a watch of window 1, all in one tick.
+    fn watch(state: &mut WindowState, events: Vec<Event>) {
+        let mut script = ScriptedEvents::new();
+        for event in events {
+            script.add(0, event);
+        }
+        let mut watch = Watch::new(state, &mut script);
+        while watch.poll_event(0).is_some() {}
+    }
+
+    #[test]
+    fn hiding_pauses_until_restored() {
+        let policy = AutoPause::new(5);
+        let mut state = WindowState::new(1);
+        assert!(!state.is_paused(&policy));
+
+        watch(&mut state, vec![window(1, WindowEventId::Hidden, 0, 0)]);
+        assert!(state.is_hidden());
+        assert!(state.is_paused(&policy));
+
+        watch(&mut state, vec![window(1, WindowEventId::Restored, 0, 0)]);
+        assert!(!state.is_hidden());
+        assert!(!state.is_paused(&policy));
+    }
+
+    #[test]
+    fn focus_loss_only_pauses_when_asked_to() {
+        let mut state = WindowState::new(1);
+        watch(&mut state, vec![window(1, WindowEventId::FocusLost, 0, 0)]);
+        assert!(!state.is_paused(&AutoPause::new(5)));
+        assert!(state.is_paused(&AutoPause::with_focus_loss(5)));
+
+        watch(&mut state, vec![window(1, WindowEventId::FocusGained, 0, 0)]);
+        assert!(!state.is_paused(&AutoPause::with_focus_loss(5)));
+    }
+
+    #[test]
+    fn resizes_are_taken_once() {
+        let mut state = WindowState::new(1);
+        watch(&mut state, vec![window(1, WindowEventId::SizeChanged, 10, 10)]);
+        assert!(state.take_resized());
+        assert!(!state.take_resized());
+    }
+
+    #[test]
+    fn events_of_other_windows_are_ignored() {
+        let mut state = WindowState::new(1);
+        watch(&mut state,
+              vec![window(2, WindowEventId::Hidden, 0, 0),
+                   window(2, WindowEventId::SizeChanged, 10, 10)]);
+        assert!(!state.is_hidden());
+        assert!(!state.take_resized());
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_paused_frame_rate_is_rejected() {
+        AutoPause::new(0);
+    }
+}
@@ -0,0 +1,191 @@
+//! Tests of the audio player, run on SDL's dummy audio driver.
+
+extern crate glorious;
+extern crate sdl2;
+extern crate sdl2_ttf;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use glorious::{AudioCommand, AudioPlayer, Device, Frame, Mixer, MusicCommand, Playlist, Plugin,
+               ResourceManager, Track};
+
+const SAMPLE_RATE: u32 = 22050;
+
+// SDL and the mixer are global, so only one test can use them at a time
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Writes a WAV file of silence lasting `millis`.
+fn write_silence(path: &Path, millis: u32) {
+    let data_len = SAMPLE_RATE * millis / 1000 * 2;
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // Mono 16-bit PCM
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.resize(wav.len() + data_len as usize, 0);
+    File::create(path).unwrap().write_all(&wav).unwrap();
+}
+
+/// Writes the sounds used by the tests to a new directory.
+fn assets() -> PathBuf {
+    let dir = env::temp_dir().join(format!("glorious-audio-test-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    write_silence(&dir.join("silence.wav"), 1000);
+    write_silence(&dir.join("short.wav"), 100);
+    dir
+}
+
+/// Runs `test` with a player on a mixer of `channels` channels, and
+/// returns the errors the player reported.
+fn with_player<F>(channels: usize, test: F) -> Vec<String>
+    where F: FnOnce(&mut AudioPlayer, &Mixer, &ResourceManager)
+{
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    env::set_var("SDL_AUDIODRIVER", "dummy");
+    let sdl = sdl2::init().unwrap();
+    let _audio = sdl.audio().unwrap();
+    let ttf = sdl2_ttf::init().unwrap();
+    let device = Device::software(16, 16).unwrap();
+    let dir = assets();
+
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    {
+        let mixer = Mixer::open(channels).unwrap();
+        let resources = ResourceManager::with_prefix(dir.clone(), &device, &ttf);
+        let mut player = AudioPlayer::new(&mixer, &resources);
+        let reported = errors.clone();
+        player.set_error_handler(move |err| reported.borrow_mut().push(err.to_string()));
+        test(&mut player, &mixer, &resources);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+    let errors = errors.borrow().clone();
+    errors
+}
+
+fn send(player: &mut AudioPlayer, command: AudioCommand) {
+    Plugin::message(player, &Frame::new(), &command);
+    Plugin::<AudioCommand>::after_messages(player, &Frame::new());
+}
+
+#[test]
+fn missing_files_are_reported() {
+    let errors = with_player(4, |player, _mixer, resources| {
+        assert!(resources.try_sound("missing.wav").is_err());
+        assert!(resources.try_music("missing.wav").is_err());
+        send(player, AudioCommand::PlaySound("missing.wav".into()));
+        let track = Track::new("missing.wav");
+        send(player, AudioCommand::Music(MusicCommand::Play(track, Default::default())));
+    });
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors[0].contains("PlaySound"), "{:?}", errors);
+    assert!(errors[1].contains("missing.wav"), "{:?}", errors);
+}
+
+#[test]
+fn busy_channels_are_not_reported() {
+    let errors = with_player(1, |player, mixer, _resources| {
+        send(player, AudioCommand::PlaySound("silence.wav".into()));
+        assert_eq!(mixer.free_channels(), 0);
+        send(player, AudioCommand::PlaySound("silence.wav".into()));
+        assert_eq!(mixer.playing_channels(), 1);
+    });
+    assert_eq!(errors, Vec::<String>::new());
+}
+
+#[test]
+fn reserved_channels_are_left_free() {
+    let errors = with_player(2, |player, mixer, _resources| {
+        assert_eq!(mixer.reserve_channels(1), 1);
+        send(player, AudioCommand::PlaySound("silence.wav".into()));
+        send(player, AudioCommand::PlaySound("silence.wav".into()));
+        assert_eq!(mixer.free_channels(), 0);
+        assert_eq!(mixer.playing_channels(), 1);
+    });
+    assert_eq!(errors, Vec::<String>::new());
+}
+
+fn play(track: Track, fade_ms: u64) -> AudioCommand {
+    AudioCommand::Music(MusicCommand::Play(track, Duration::from_millis(fade_ms)))
+}
+
+/// Lets the player poll its jukebox every 20 ms for `millis`.
+fn poll_for(player: &mut AudioPlayer, millis: u64) {
+    for _ in 0..millis / 20 {
+        thread::sleep(Duration::from_millis(20));
+        Plugin::<AudioCommand>::after_messages(player, &Frame::new());
+    }
+}
+
+#[test]
+fn switching_tracks_fades_out_first() {
+    let errors = with_player(1, |player, mixer, _resources| {
+        send(player, play(Track::looping("silence.wav", Duration::new(0, 0)), 0));
+        assert!(mixer.is_music_playing());
+        send(player, play(Track::looping("short.wav", Duration::new(0, 0)), 100));
+        // The first track fades out before the second one starts
+        assert!(mixer.is_music_playing());
+        assert_eq!(player.current_track().unwrap().path, "short.wav");
+        poll_for(player, 300);
+        assert!(mixer.is_music_playing());
+        assert_eq!(player.current_track().unwrap().path, "short.wav");
+    });
+    assert_eq!(errors, Vec::<String>::new());
+}
+
+#[test]
+fn stopping_fades_the_music_out() {
+    let errors = with_player(1, |player, mixer, _resources| {
+        send(player, play(Track::looping("silence.wav", Duration::new(0, 0)), 0));
+        let stop = MusicCommand::Stop(Duration::from_millis(100));
+        send(player, AudioCommand::Music(stop));
+        assert_eq!(player.current_track(), None);
+        poll_for(player, 300);
+        assert!(!mixer.is_music_playing());
+    });
+    assert_eq!(errors, Vec::<String>::new());
+}
+
+#[test]
+fn looping_tracks_continue_from_their_loop_point() {
+    let errors = with_player(1, |player, mixer, _resources| {
+        send(player, play(Track::looping("short.wav", Duration::from_millis(50)), 0));
+        // Several times the length of the track
+        poll_for(player, 400);
+        assert!(mixer.is_music_playing());
+        assert_eq!(player.current_track().unwrap().path, "short.wav");
+    });
+    assert_eq!(errors, Vec::<String>::new());
+}
+
+#[test]
+fn playlists_move_on_after_a_track() {
+    let errors = with_player(1, |player, _mixer, _resources| {
+        let playlist = Playlist::new(vec![Track::new("short.wav"), Track::new("missing.wav")]);
+        let command = MusicCommand::Playlist(playlist, Duration::new(0, 0));
+        send(player, AudioCommand::Music(command));
+        assert_eq!(player.current_track().unwrap().path, "short.wav");
+        thread::sleep(Duration::from_millis(300));
+        Plugin::<AudioCommand>::after_messages(player, &Frame::new());
+        assert_eq!(player.current_track(), None);
+    });
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].starts_with("could not play the next track"), "{:?}", errors);
+}
@@ -0,0 +1,107 @@
+//! Tests of screenshots and image sequences of `Game`, run without a
+//! window.
+
+extern crate glorious;
+extern crate sdl2;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod};
+
+use glorious::{Behavior, BoxedInputMapper, Capture, Device, Game, InputPattern, ScriptedEvents};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Message {
+    Quit,
+    F12,
+}
+
+struct Idle;
+
+impl Behavior<()> for Idle {
+    type Message = Message;
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("glorious-capture-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn run(events: ScriptedEvents, capture: Capture) -> Message {
+    let device = Device::software(16, 16).unwrap();
+    let mut mapper = BoxedInputMapper::new();
+    mapper.add_pattern(InputPattern::quit(), Message::Quit);
+    mapper.add_pattern(InputPattern::key_pressed(0, Keycode::F12, false, None), Message::F12);
+
+    let mut game = Game::new(1000, device.create_renderer(), events);
+    game.set_capture(Some(capture));
+    game.run(&mut (), &mapper, &mut Idle, |&m| Some(m))
+}
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn hotkey_takes_screenshot() {
+    let dir = temp_dir("hotkey");
+    let mut events = ScriptedEvents::new();
+    events.add(0,
+               Event::KeyDown {
+                   timestamp: 0,
+                   window_id: 0,
+                   keycode: Some(Keycode::F12),
+                   scancode: None,
+                   keymod: Mod::empty(),
+                   repeat: false,
+               });
+    events.add(2, Event::Quit { timestamp: 0 });
+
+    // The hotkey is not passed on to the game
+    assert_eq!(run(events, Capture::new(&dir)), Message::Quit);
+    assert_eq!(files(&dir), vec!["screenshot-000000.png"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn sequence_saves_every_nth_frame() {
+    let dir = temp_dir("sequence");
+    let mut capture = Capture::new(&dir);
+    capture.set_sequence(Some(2));
+    let mut events = ScriptedEvents::new();
+    events.add(5, Event::Quit { timestamp: 0 });
+
+    run(events, capture);
+    assert_eq!(files(&dir),
+               vec!["frame-000000.png", "frame-000001.png", "frame-000002.png"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failures_are_reported() {
+    // A directory cannot be created inside a file
+    let file = temp_dir("file");
+    File::create(&file).unwrap();
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    let mut capture = Capture::new(file.join("images"));
+    let reported = errors.clone();
+    capture.set_error_handler(move |err| reported.borrow_mut().push(err.path.clone()));
+    capture.request_screenshot();
+    let mut events = ScriptedEvents::new();
+    events.add(1, Event::Quit { timestamp: 0 });
+
+    run(events, capture);
+    assert_eq!(*errors.borrow(), vec![file.join("images/screenshot-000000.png")]);
+    fs::remove_file(&file).unwrap();
+}
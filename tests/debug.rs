@@ -0,0 +1,67 @@
+//! Tests of the debug controls of `Game`, run without a window.
+
+extern crate glorious;
+extern crate sdl2;
+
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod};
+
+use glorious::{Behavior, BoxedInputMapper, DebugControls, Device, Frame, Game, InputPattern,
+               Queue, ScriptedEvents};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Message {
+    Quit,
+}
+
+struct Counter;
+
+impl Behavior<u32> for Counter {
+    type Message = Message;
+
+    fn update(&mut self, updates: &mut u32, _frame: &Frame, _queue: &mut Queue<Message>) {
+        *updates += 1;
+    }
+}
+
+fn key_down(keycode: Keycode) -> Event {
+    Event::KeyDown {
+        timestamp: 0,
+        window_id: 0,
+        keycode: Some(keycode),
+        scancode: None,
+        keymod: Mod::empty(),
+        repeat: false,
+    }
+}
+
+fn run(events: ScriptedEvents) -> (Message, u32) {
+    let device = Device::software(16, 16).unwrap();
+    let mut mapper = BoxedInputMapper::new();
+    mapper.add_pattern(InputPattern::quit(), Message::Quit);
+
+    let mut game = Game::new(1000, device.create_renderer(), events);
+    game.set_debug_controls(Some(DebugControls::new()));
+    let mut updates = 0;
+    let message = game.run(&mut updates, &mapper, &mut Counter, |&m| Some(m));
+    (message, updates)
+}
+
+#[test]
+fn quit_while_paused() {
+    let mut events = ScriptedEvents::new();
+    events.add(0, key_down(Keycode::F5));
+    events.add(0, Event::Quit { timestamp: 0 });
+
+    assert_eq!(run(events), (Message::Quit, 0));
+}
+
+#[test]
+fn quit_after_stepping() {
+    let mut events = ScriptedEvents::new();
+    events.add(0, key_down(Keycode::F5));
+    events.add(0, key_down(Keycode::F6));
+    events.add(1, Event::Quit { timestamp: 0 });
+
+    assert_eq!(run(events), (Message::Quit, 1));
+}
@@ -0,0 +1,101 @@
+//! Tests of when `Headless` calls the hooks of its plugins.
+
+extern crate glorious;
+extern crate sdl2;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use sdl2::event::Event;
+
+use glorious::{Behavior, BoxedInputMapper, Device, Frame, Headless, Plugin, Renderer,
+               ScriptedEvents};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Calls {
+    before_events: u32,
+    events: u32,
+    after_events: u32,
+    before_messages: u32,
+    after_messages: u32,
+    before_update: u32,
+    after_update: u32,
+    before_render: u32,
+    after_render: u32,
+}
+
+struct Counter(Rc<RefCell<Calls>>);
+
+impl Plugin<()> for Counter {
+    fn before_events(&mut self, _frame: &Frame) {
+        self.0.borrow_mut().before_events += 1;
+    }
+
+    fn event(&mut self, _frame: &Frame, _event: &Event) {
+        self.0.borrow_mut().events += 1;
+    }
+
+    fn after_events(&mut self, _frame: &Frame) {
+        self.0.borrow_mut().after_events += 1;
+    }
+
+    fn before_messages(&mut self, _frame: &Frame) {
+        self.0.borrow_mut().before_messages += 1;
+    }
+
+    fn after_messages(&mut self, _frame: &Frame) {
+        self.0.borrow_mut().after_messages += 1;
+    }
+
+    fn before_update(&mut self, _frame: &Frame) {
+        self.0.borrow_mut().before_update += 1;
+    }
+
+    fn after_update(&mut self, _frame: &Frame) {
+        self.0.borrow_mut().after_update += 1;
+    }
+
+    fn before_render(&mut self, _frame: &Frame, _renderer: &mut Renderer) {
+        self.0.borrow_mut().before_render += 1;
+    }
+
+    fn after_render(&mut self, _frame: &Frame, _renderer: &mut Renderer) {
+        self.0.borrow_mut().after_render += 1;
+    }
+}
+
+struct Idle;
+
+impl Behavior<()> for Idle {
+    type Message = ();
+}
+
+#[test]
+fn events_are_polled_once_per_frame() {
+    let device = Device::software(16, 16).unwrap();
+    let mut events = ScriptedEvents::new();
+    events.add(0, Event::Quit { timestamp: 0 });
+    events.add(3, Event::Quit { timestamp: 0 });
+
+    let calls = Rc::new(RefCell::new(Calls::default()));
+    let mut game = Headless::new(device.create_renderer(), events);
+    game.add_plugin(Counter(calls.clone()));
+    // Two ticks per frame
+    game.set_frame_time(Duration::from_millis(20));
+    game.set_tick_rate(Some(100));
+    game.run(3, &mut (), &BoxedInputMapper::new(), &mut Idle);
+
+    assert_eq!(*calls.borrow(),
+               Calls {
+                   before_events: 3,
+                   events: 2,
+                   after_events: 3,
+                   before_messages: 6,
+                   after_messages: 6,
+                   before_update: 6,
+                   after_update: 6,
+                   before_render: 3,
+                   after_render: 3,
+               });
+}
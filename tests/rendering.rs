@@ -0,0 +1,109 @@
+//! Golden-image tests of the rendering code, run on a software
+//! renderer with `--features golden`.
+//!
+//! Set `GLORIOUS_BLESS` to write missing reference images in
+//! `tests/golden`, or to update them after an intended change.
+
+extern crate glorious;
+extern crate sdl2_ttf;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use glorious::{Asset, BLESS_VAR, Color, GoldenError, GoldenImages, Label, Rect, Sprite};
+
+fn golden() -> GoldenImages {
+    GoldenImages::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"))
+}
+
+#[test]
+fn fill_rect() {
+    golden()
+        .render("fill_rect", 32, 32, |renderer| {
+            renderer.set_draw_color(Color(0xff, 0x00, 0x00, 0xff));
+            renderer.fill_rect(Rect::new(8, 8, 16, 16)).unwrap();
+        })
+        .unwrap();
+}
+
+#[test]
+fn sprite() {
+    let mut golden = golden();
+    // Allow for rounding when blending
+    golden.set_tolerance(1);
+    golden.render("sprite", 32, 32, |renderer| {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/raccoon.png");
+            let texture = renderer.device().load_texture(path).unwrap();
+            let sprite = Sprite::new(Rc::new(Asset::new(texture)), None);
+            sprite.render(renderer, 0, 0, None);
+        })
+        .unwrap();
+}
+
+#[test]
+fn label() {
+    let ttf = sdl2_ttf::init().unwrap();
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/DejaVuSansMono.ttf");
+    let font = ttf.load_font(path.as_ref(), 12).unwrap();
+    let mut golden = golden();
+    // Allow for rounding when blending the antialiased glyphs
+    golden.set_tolerance(1);
+    golden.render("label", 32, 16, |renderer| {
+            let label = Label::new(&font, "Ab1", Color(0xff, 0xff, 0xff, 0xff), renderer.device());
+            label.render(renderer, 2, 0);
+        })
+        .unwrap();
+}
+
+/// Returns an empty directory for references, for tests that do not
+/// use the committed ones.
+fn temp_golden(name: &str) -> (PathBuf, GoldenImages) {
+    let dir = env::temp_dir().join(format!("glorious-golden-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    (dir.clone(), GoldenImages::new(dir))
+}
+
+fn fill(renderer: &mut glorious::Renderer) {
+    renderer.set_draw_color(Color(0x00, 0x00, 0xff, 0xff));
+    renderer.fill_rect(Rect::new(2, 2, 4, 4)).unwrap();
+}
+
+#[test]
+fn missing_reference_fails() {
+    if env::var_os(BLESS_VAR).is_some() {
+        // Blessing writes the reference instead
+        return;
+    }
+    let (dir, golden) = temp_golden("missing");
+    match golden.render("missing", 8, 8, fill) {
+        Err(GoldenError::MissingReference { ref reference, ref actual }) => {
+            assert!(!reference.exists());
+            assert!(actual.is_file());
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn size_mismatch_writes_diff() {
+    if env::var_os(BLESS_VAR).is_some() {
+        return;
+    }
+    let (dir, golden) = temp_golden("size");
+    fs::copy(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/fill_rect.png"),
+             dir.join("fill_rect.png"))
+        .unwrap();
+    match golden.render("fill_rect", 16, 16, fill) {
+        Err(GoldenError::SizeMismatch { expected_size, actual_size, ref actual, ref diff }) => {
+            assert_eq!((expected_size, actual_size), ((32, 32), (16, 16)));
+            assert!(actual.is_file());
+            assert!(diff.is_file());
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}